@@ -0,0 +1,153 @@
+use crate::framebuffer::Framebuffer;
+
+// Suavizado de bordes seleccionable en tiempo de ejecución
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AntiAliasing {
+    Off,
+    // 4 muestras de cobertura/profundidad por píxel, un sombreado por píxel
+    Msaa4x,
+    // post-proceso sobre la imagen ya resuelta: barato pero emborrona algo el detalle
+    Fxaa,
+}
+
+impl AntiAliasing {
+    pub fn next(self) -> Self {
+        match self {
+            AntiAliasing::Off => AntiAliasing::Msaa4x,
+            AntiAliasing::Msaa4x => AntiAliasing::Fxaa,
+            AntiAliasing::Fxaa => AntiAliasing::Off,
+        }
+    }
+
+    // Muestras por píxel que debe usar el framebuffer
+    pub fn samples(self) -> usize {
+        match self {
+            AntiAliasing::Msaa4x => 4,
+            AntiAliasing::Off | AntiAliasing::Fxaa => 1,
+        }
+    }
+
+    // Nombre para el HUD
+    pub fn label(self) -> &'static str {
+        match self {
+            AntiAliasing::Off => "sin AA",
+            AntiAliasing::Msaa4x => "MSAA 4x",
+            AntiAliasing::Fxaa => "FXAA",
+        }
+    }
+}
+
+// Contraste mínimo (absoluto y relativo al máximo local) para tratar un píxel como borde
+const EDGE_THRESHOLD_MIN: f32 = 0.0312;
+const EDGE_THRESHOLD: f32 = 0.125;
+// Pasos de búsqueda a lo largo del borde y cuánto pesa el suavizado sub-píxel
+const SEARCH_STEPS: i32 = 8;
+const SUBPIXEL_QUALITY: f32 = 0.75;
+
+// FXAA (versión "quality" simplificada) sobre `buffer`, después de `resolve`:
+// detecta bordes por contraste de luma, estima su orientación y longitud y mezcla
+// cada píxel con su vecino al otro lado del borde en proporción a su posición en él.
+pub fn fxaa(framebuffer: &mut Framebuffer) {
+    let (w, h) = (framebuffer.width as i32, framebuffer.height as i32);
+    let src = framebuffer.buffer.clone();
+    let rgb = |x: i32, y: i32| unpack(src[(y.clamp(0, h - 1) * w + x.clamp(0, w - 1)) as usize]);
+    let luma = |x: i32, y: i32| {
+        let [r, g, b] = rgb(x, y);
+        0.299 * r + 0.587 * g + 0.114 * b
+    };
+
+    for y in 0..h {
+        for x in 0..w {
+            let center = luma(x, y);
+            let (north, south, east, west) = (luma(x, y - 1), luma(x, y + 1), luma(x + 1, y), luma(x - 1, y));
+            let max = center.max(north).max(south).max(east).max(west);
+            let min = center.min(north).min(south).min(east).min(west);
+            let range = max - min;
+            if range < EDGE_THRESHOLD_MIN.max(max * EDGE_THRESHOLD) {
+                continue;
+            }
+
+            let (nw, ne, sw, se) = (luma(x - 1, y - 1), luma(x + 1, y - 1), luma(x - 1, y + 1), luma(x + 1, y + 1));
+            // orientación: el gradiente dominante dice si el borde es horizontal o vertical
+            let horizontal_grad = (nw + ne - 2.0 * north).abs() + 2.0 * (west + east - 2.0 * center).abs() + (sw + se - 2.0 * south).abs();
+            let vertical_grad = (nw + sw - 2.0 * west).abs() + 2.0 * (north + south - 2.0 * center).abs() + (ne + se - 2.0 * east).abs();
+            let horizontal = horizontal_grad >= vertical_grad;
+
+            // lado del borde con más contraste: hacia allí se desplaza la muestra
+            let (l_neg, l_pos) = if horizontal { (north, south) } else { (west, east) };
+            let (grad_neg, grad_pos) = ((l_neg - center).abs(), (l_pos - center).abs());
+            let (step, side_luma, gradient) =
+                if grad_neg >= grad_pos { (-1.0, l_neg, grad_neg) } else { (1.0, l_pos, grad_pos) };
+            let edge_luma = (center + side_luma) * 0.5;
+            let scaled_gradient = gradient * 0.25;
+
+            // recorre el borde en ambos sentidos hasta que la luma media cambia
+            let (ox, oy) = if horizontal { (0.0, step * 0.5) } else { (step * 0.5, 0.0) };
+            let (dx, dy) = if horizontal { (1.0, 0.0) } else { (0.0, 1.0) };
+            let sample_luma = |px: f32, py: f32| {
+                let [r, g, b] = bilinear(&rgb, px, py);
+                0.299 * r + 0.587 * g + 0.114 * b
+            };
+            let (cx, cy) = (x as f32 + ox, y as f32 + oy);
+            let mut dist_neg = SEARCH_STEPS as f32;
+            let mut dist_pos = SEARCH_STEPS as f32;
+            let mut end_neg = edge_luma;
+            let mut end_pos = edge_luma;
+            for i in 1..=SEARCH_STEPS {
+                let l = sample_luma(cx - dx * i as f32, cy - dy * i as f32);
+                if (l - edge_luma).abs() >= scaled_gradient {
+                    dist_neg = i as f32;
+                    end_neg = l;
+                    break;
+                }
+            }
+            for i in 1..=SEARCH_STEPS {
+                let l = sample_luma(cx + dx * i as f32, cy + dy * i as f32);
+                if (l - edge_luma).abs() >= scaled_gradient {
+                    dist_pos = i as f32;
+                    end_pos = l;
+                    break;
+                }
+            }
+
+            // desplazamiento según la distancia al extremo más cercano del borde,
+            // sólo si ese extremo va en el sentido correcto del contraste
+            let center_below = center < edge_luma;
+            let (dist, end) = if dist_neg < dist_pos { (dist_neg, end_neg) } else { (dist_pos, end_pos) };
+            let edge_offset = if ((end - edge_luma) < 0.0) != center_below { 0.5 - dist / (dist_neg + dist_pos) } else { 0.0 };
+
+            // suavizado sub-píxel para detalles de un píxel (estrellas, bordes finos)
+            let average = (2.0 * (north + south + east + west) + nw + ne + sw + se) / 12.0;
+            let sub = ((average - center).abs() / range).clamp(0.0, 1.0);
+            let sub = (-2.0 * sub + 3.0) * sub * sub;
+            let subpixel_offset = sub * sub * SUBPIXEL_QUALITY;
+
+            let offset = edge_offset.max(subpixel_offset) * step;
+            let (px, py) = if horizontal { (x as f32, y as f32 + offset) } else { (x as f32 + offset, y as f32) };
+            framebuffer.buffer[(y * w + x) as usize] = pack(bilinear(&rgb, px, py));
+        }
+    }
+}
+
+fn unpack(px: u32) -> [f32; 3] {
+    [((px >> 16) & 0xFF) as f32 / 255.0, ((px >> 8) & 0xFF) as f32 / 255.0, (px & 0xFF) as f32 / 255.0]
+}
+
+fn pack([r, g, b]: [f32; 3]) -> u32 {
+    let q = |c: f32| (c.clamp(0.0, 1.0) * 255.0).round() as u32;
+    (q(r) << 16) | (q(g) << 8) | q(b)
+}
+
+// Muestreo bilineal de la imagen en coordenadas de píxel (enteros = centros)
+fn bilinear(rgb: &impl Fn(i32, i32) -> [f32; 3], x: f32, y: f32) -> [f32; 3] {
+    let (x0, y0) = (x.floor() as i32, y.floor() as i32);
+    let (tx, ty) = (x - x0 as f32, y - y0 as f32);
+    let (a, b, c, d) = (rgb(x0, y0), rgb(x0 + 1, y0), rgb(x0, y0 + 1), rgb(x0 + 1, y0 + 1));
+    let mut out = [0.0; 3];
+    for i in 0..3 {
+        let top = a[i] + (b[i] - a[i]) * tx;
+        let bottom = c[i] + (d[i] - c[i]) * tx;
+        out[i] = top + (bottom - top) * ty;
+    }
+    out
+}
@@ -0,0 +1,153 @@
+use nalgebra_glm::Vec3;
+use crate::light::Light;
+
+// Parámetros de dispersión simple (Rayleigh + Mie) de la atmósfera de un cuerpo.
+// Coeficientes y alturas de escala son relativos al radio del planeta, así la
+// atmósfera se ve igual al cambiar la escala del cuerpo en los modos de foco.
+#[derive(Debug, Clone, Copy)]
+pub struct Atmosphere {
+    pub center: Vec3,
+    pub planet_radius: f32,
+    // radio de la capa / radio del planeta
+    pub shell_scale: f32,
+    pub rayleigh: Vec3,
+    pub mie: f32,
+    // alturas de escala como fracción del grosor de la capa
+    pub rayleigh_height: f32,
+    pub mie_height: f32,
+    // asimetría de Henyey-Greenstein para Mie (hacia adelante si > 0)
+    pub mie_g: f32,
+    pub intensity: f32,
+}
+
+const VIEW_SAMPLES: usize = 10;
+const LIGHT_SAMPLES: usize = 4;
+
+impl Atmosphere {
+    // Azul en el limbo y terminador anaranjado, como la Tierra
+    pub fn earth_like(center: Vec3, planet_radius: f32) -> Self {
+        Self {
+            center,
+            planet_radius,
+            shell_scale: 1.1,
+            rayleigh: Vec3::new(0.70, 1.65, 4.00),
+            mie: 0.6,
+            rayleigh_height: 0.25,
+            mie_height: 0.1,
+            mie_g: 0.76,
+            intensity: 14.0,
+        }
+    }
+
+    // Bruma cálida y tenue para el gigante gaseoso
+    pub fn hazy(center: Vec3, planet_radius: f32) -> Self {
+        Self {
+            center,
+            planet_radius,
+            shell_scale: 1.05,
+            rayleigh: Vec3::new(1.2, 1.0, 0.7),
+            mie: 1.5,
+            rayleigh_height: 0.35,
+            mie_height: 0.2,
+            mie_g: 0.6,
+            intensity: 3.0,
+        }
+    }
+
+    pub fn shell_radius(&self) -> f32 {
+        self.planet_radius * self.shell_scale
+    }
+
+    // Luz dispersada hacia la cámara a lo largo del rayo `origin + t * dir`
+    // (dir normalizada). Devuelve rgb lineal sin limitar.
+    pub fn scatter(&self, origin: Vec3, dir: Vec3, lights: &[Light]) -> Vec3 {
+        let outer = self.shell_radius();
+        let Some((t0, mut t1)) = ray_sphere(origin, dir, self.center, outer) else {
+            return Vec3::zeros();
+        };
+        let t0 = t0.max(0.0);
+        if let Some((tp, _)) = ray_sphere(origin, dir, self.center, self.planet_radius)
+            && tp > 0.0
+        {
+            t1 = t1.min(tp);
+        }
+        if t1 <= t0 {
+            return Vec3::zeros();
+        }
+
+        let thickness = outer - self.planet_radius;
+        let hr = self.rayleigh_height * thickness;
+        let hm = self.mie_height * thickness;
+        let beta_r = self.rayleigh / self.planet_radius;
+        let beta_m = self.mie / self.planet_radius;
+
+        let mut result = Vec3::zeros();
+        for light in lights {
+            let seg = (t1 - t0) / VIEW_SAMPLES as f32;
+            let mut od_r = 0.0;
+            let mut od_m = 0.0;
+            let mut sum_r = Vec3::zeros();
+            let mut sum_m = Vec3::zeros();
+            for i in 0..VIEW_SAMPLES {
+                let p = origin + dir * (t0 + seg * (i as f32 + 0.5));
+                let h = ((p - self.center).norm() - self.planet_radius).max(0.0);
+                let dr = (-h / hr).exp() * seg;
+                let dm = (-h / hm).exp() * seg;
+                od_r += dr;
+                od_m += dm;
+
+                let Some((lr, lm)) = self.light_depth(p, (light.position - p).normalize(), hr, hm) else {
+                    continue; // el planeta tapa la luz en este punto
+                };
+                let tau = beta_r * (od_r + lr) + Vec3::repeat(beta_m * 1.1 * (od_m + lm));
+                let attn = Vec3::new((-tau.x).exp(), (-tau.y).exp(), (-tau.z).exp());
+                sum_r += attn * dr;
+                sum_m += attn * dm;
+            }
+
+            let mu = dir.dot(&(light.position - self.center).normalize());
+            let phase_r = 3.0 / (16.0 * std::f32::consts::PI) * (1.0 + mu * mu);
+            let g = self.mie_g;
+            let phase_m = 3.0 / (8.0 * std::f32::consts::PI) * ((1.0 - g * g) * (1.0 + mu * mu))
+                / ((2.0 + g * g) * (1.0 + g * g - 2.0 * g * mu).powf(1.5));
+
+            let light_rgb = Vec3::new(light.color.r, light.color.g, light.color.b);
+            let inscatter = sum_r.component_mul(&beta_r) * phase_r + sum_m * beta_m * phase_m;
+            result += inscatter.component_mul(&light_rgb) * (self.intensity * light.intensity);
+        }
+        result
+    }
+
+    // Profundidad óptica (Rayleigh, Mie) desde `p` hacia la luz; None si el planeta la bloquea
+    fn light_depth(&self, p: Vec3, l: Vec3, hr: f32, hm: f32) -> Option<(f32, f32)> {
+        if let Some((tp, _)) = ray_sphere(p, l, self.center, self.planet_radius)
+            && tp > 0.0
+        {
+            return None;
+        }
+        let (_, t1) = ray_sphere(p, l, self.center, self.shell_radius())?;
+        let seg = t1.max(0.0) / LIGHT_SAMPLES as f32;
+        let mut od_r = 0.0;
+        let mut od_m = 0.0;
+        for i in 0..LIGHT_SAMPLES {
+            let q = p + l * (seg * (i as f32 + 0.5));
+            let h = ((q - self.center).norm() - self.planet_radius).max(0.0);
+            od_r += (-h / hr).exp() * seg;
+            od_m += (-h / hm).exp() * seg;
+        }
+        Some((od_r, od_m))
+    }
+}
+
+// Intersección rayo-esfera: (t entrada, t salida) si el rayo la cruza
+fn ray_sphere(origin: Vec3, dir: Vec3, center: Vec3, radius: f32) -> Option<(f32, f32)> {
+    let oc = origin - center;
+    let b = oc.dot(&dir);
+    let c = oc.dot(&oc) - radius * radius;
+    let disc = b * b - c;
+    if disc < 0.0 {
+        return None;
+    }
+    let s = disc.sqrt();
+    Some((-b - s, -b + s))
+}
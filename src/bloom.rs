@@ -0,0 +1,166 @@
+use crate::{color::Color, framebuffer::Framebuffer};
+
+// Niveles de la cadena, cada uno a la mitad del anterior
+const LEVELS: usize = 4;
+// Alto aproximado del primer nivel: fijarlo en vez de usar siempre la mitad hace que
+// el halo ocupe lo mismo de la imagen a cualquier resolución (capturas en alta)
+const FIRST_LEVEL_HEIGHT: usize = 400;
+// Gaussiana de 9 muestras (centro + 4 por lado), sigma ~2 px del nivel
+const KERNEL: [f32; 5] = [0.227_027, 0.194_594_6, 0.121_621_6, 0.054_054_05, 0.016_216_22];
+
+// Una imagen reducida de la cadena, con su buffer auxiliar para el desenfoque
+struct Level {
+    width: usize,
+    height: usize,
+    data: Vec<Color>,
+    scratch: Vec<Color>,
+}
+
+impl Level {
+    fn new(width: usize, height: usize) -> Self {
+        let black = Color::linear(0.0, 0.0, 0.0);
+        Self { width, height, data: vec![black; width * height], scratch: vec![black; width * height] }
+    }
+
+    // Muestreo bilineal en coordenadas de píxel del nivel (centros en i + 0.5)
+    fn sample(&self, x: f32, y: f32) -> Color {
+        let x = (x - 0.5).clamp(0.0, self.width as f32 - 1.0);
+        let y = (y - 0.5).clamp(0.0, self.height as f32 - 1.0);
+        let (x0, y0) = (x as usize, y as usize);
+        let (x1, y1) = ((x0 + 1).min(self.width - 1), (y0 + 1).min(self.height - 1));
+        let (tx, ty) = (x - x0 as f32, y - y0 as f32);
+        let at = |x: usize, y: usize| self.data[y * self.width + x];
+        let top = at(x0, y0) * (1.0 - tx) + at(x1, y0) * tx;
+        let bottom = at(x0, y1) * (1.0 - tx) + at(x1, y1) * tx;
+        top * (1.0 - ty) + bottom * ty
+    }
+
+    // Gaussiana separable: horizontal a `scratch`, vertical de vuelta a `data`
+    fn blur(&mut self) {
+        let (w, h) = (self.width as i32, self.height as i32);
+        for y in 0..h {
+            for x in 0..w {
+                let mut sum = self.data[(y * w + x) as usize] * KERNEL[0];
+                for (k, weight) in KERNEL.iter().enumerate().skip(1) {
+                    let k = k as i32;
+                    sum = sum + self.data[(y * w + (x - k).max(0)) as usize] * *weight;
+                    sum = sum + self.data[(y * w + (x + k).min(w - 1)) as usize] * *weight;
+                }
+                self.scratch[(y * w + x) as usize] = sum;
+            }
+        }
+        for y in 0..h {
+            for x in 0..w {
+                let mut sum = self.scratch[(y * w + x) as usize] * KERNEL[0];
+                for (k, weight) in KERNEL.iter().enumerate().skip(1) {
+                    let k = k as i32;
+                    sum = sum + self.scratch[((y - k).max(0) * w + x) as usize] * *weight;
+                    sum = sum + self.scratch[((y + k).min(h - 1) * w + x) as usize] * *weight;
+                }
+                self.data[(y * w + x) as usize] = sum;
+            }
+        }
+    }
+}
+
+// Bloom sobre el buffer HDR, antes del tone mapping: filtro de brillo con umbral,
+// desenfoque gaussiano en una cadena de buffers reducidos y suma sobre la imagen.
+pub struct Bloom {
+    // luminancia a partir de la que un píxel brilla (con rodilla suave por debajo)
+    pub threshold: f32,
+    // peso con el que se suma el resultado
+    pub intensity: f32,
+    levels: Vec<Level>,
+}
+
+impl Bloom {
+    pub fn new(threshold: f32, intensity: f32) -> Self {
+        Self { threshold, intensity, levels: Vec::new() }
+    }
+
+    pub fn apply(&mut self, framebuffer: &mut Framebuffer) {
+        if self.intensity <= 0.0 {
+            return;
+        }
+        let factor = (framebuffer.height as f32 / FIRST_LEVEL_HEIGHT as f32).round().max(2.0) as usize;
+        self.allocate(framebuffer.width / factor, framebuffer.height / factor);
+
+        // primer nivel: media de bloques del framebuffer y filtro de brillo
+        let knee = self.threshold * 0.5;
+        let threshold = self.threshold;
+        let first = &mut self.levels[0];
+        for y in 0..first.height {
+            for x in 0..first.width {
+                let c = average_block(&framebuffer.color, framebuffer.width, framebuffer.height, factor, x, y);
+                first.data[y * first.width + x] = bright_pass(c, threshold, knee);
+            }
+        }
+        first.blur();
+
+        // cada nivel reduce el anterior ya desenfocado: el halo se ensancha sin más muestras
+        for i in 1..self.levels.len() {
+            let (done, rest) = self.levels.split_at_mut(i);
+            let (prev, level) = (&done[i - 1], &mut rest[0]);
+            for y in 0..level.height {
+                for x in 0..level.width {
+                    level.data[y * level.width + x] = average_block(&prev.data, prev.width, prev.height, 2, x, y);
+                }
+            }
+            level.blur();
+        }
+
+        // composición aditiva de todos los niveles a resolución completa
+        let weight = self.intensity / self.levels.len() as f32;
+        for y in 0..framebuffer.height {
+            for x in 0..framebuffer.width {
+                let mut sum = Color::linear(0.0, 0.0, 0.0);
+                for level in &self.levels {
+                    let sx = level.width as f32 / framebuffer.width as f32;
+                    let sy = level.height as f32 / framebuffer.height as f32;
+                    sum = sum + level.sample((x as f32 + 0.5) * sx, (y as f32 + 0.5) * sy);
+                }
+                let idx = y * framebuffer.width + x;
+                framebuffer.color[idx] = framebuffer.color[idx] + sum * weight;
+            }
+        }
+    }
+
+    // (Re)crea la cadena si cambia el tamaño del primer nivel
+    fn allocate(&mut self, width: usize, height: usize) {
+        let size = |i: u32| ((width >> i).max(1), (height >> i).max(1));
+        if self.levels.first().map(|l| (l.width, l.height)) == Some(size(0)) {
+            return;
+        }
+        self.levels = (0..LEVELS as u32)
+            .map(|i| {
+                let (w, h) = size(i);
+                Level::new(w, h)
+            })
+            .collect();
+    }
+}
+
+// Media del bloque de `factor` x `factor` píxeles de `src` que cae en (x, y) del nivel reducido
+fn average_block(src: &[Color], width: usize, height: usize, factor: usize, x: usize, y: usize) -> Color {
+    let mut sum = Color::linear(0.0, 0.0, 0.0);
+    for sy in 0..factor {
+        for sx in 0..factor {
+            let (px, py) = ((factor * x + sx).min(width - 1), (factor * y + sy).min(height - 1));
+            sum = sum + src[py * width + px];
+        }
+    }
+    sum * (1.0 / (factor * factor) as f32)
+}
+
+// Deja pasar sólo lo que supera el umbral, con una transición cuadrática de
+// ancho `knee` para que no aparezca un borde duro en el halo
+fn bright_pass(c: Color, threshold: f32, knee: f32) -> Color {
+    let luminance = 0.2126 * c.r + 0.7152 * c.g + 0.0722 * c.b;
+    if luminance <= 1e-4 {
+        return Color::linear(0.0, 0.0, 0.0);
+    }
+    let soft = (luminance - threshold + knee).clamp(0.0, 2.0 * knee);
+    let soft = soft * soft / (4.0 * knee + 1e-4);
+    let contribution = soft.max(luminance - threshold) / luminance;
+    c * contribution
+}
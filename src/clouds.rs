@@ -0,0 +1,40 @@
+use fastnoise_lite::FastNoiseLite;
+use nalgebra_glm::Vec3;
+use crate::shaders::smoothstep;
+
+// Capa de nubes: una esfera algo mayor que el planeta que gira a su propio ritmo.
+// La usan el shader de nubes (color y opacidad) y el del planeta (sombra de nubes).
+#[derive(Clone, Copy)]
+pub struct CloudLayer<'a> {
+    pub noise: &'a FastNoiseLite,
+    pub center: Vec3,
+    pub radius: f32,
+    // ángulo actual de giro de la capa alrededor de Y
+    pub rotation: f32,
+    // fracción aproximada del cielo cubierta (0..1)
+    pub coverage: f32,
+    pub opacity: f32,
+}
+
+impl CloudLayer<'_> {
+    // Densidad (0..1) en una dirección del espacio de objeto de la capa
+    pub fn density(&self, dir: Vec3) -> f32 {
+        let d = dir.normalize() * 2.0;
+        let n = (self.noise.get_noise_3d(d.x, d.y, d.z) + 1.0) * 0.5;
+        let edge = 1.0 - self.coverage;
+        smoothstep(edge - 0.12, edge + 0.12, n)
+    }
+
+    // Sombra (0 = sin sombra, 1 = opaca) que proyecta la capa sobre el punto `p`
+    // de la superficie: se busca la nube desplazándose hacia la luz `l`.
+    pub fn shadow(&self, p: Vec3, l: Vec3) -> f32 {
+        let rel = p - self.center;
+        let n = rel.normalize();
+        let height = (self.radius - rel.norm()).max(0.0);
+        let q = rel + l * (height / n.dot(&l).max(0.15));
+        // mundo -> objeto: deshacer el giro de la capa
+        let (s, c) = self.rotation.sin_cos();
+        let local = Vec3::new(c * q.x - s * q.z, q.y, s * q.x + c * q.z);
+        self.density(local) * self.opacity
+    }
+}
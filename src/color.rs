@@ -1,25 +1,121 @@
+use std::sync::OnceLock;
+
+// Espacio en el que operan shaders y post-proceso. Los colores escritos a mano y
+// las texturas están en sRGB y se pasan al espacio de trabajo al dibujar
+// (`decode`); el resolve codifica a bytes (`encode`). `Gamma` opera sobre los
+// valores sRGB tal cual, como antes del pipeline lineal (para comparar).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ColorPipeline {
+    Linear,
+    Gamma,
+}
+
+impl ColorPipeline {
+    pub fn next(self) -> Self {
+        match self {
+            ColorPipeline::Linear => ColorPipeline::Gamma,
+            ColorPipeline::Gamma => ColorPipeline::Linear,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ColorPipeline::Linear => "lineal",
+            ColorPipeline::Gamma => "gamma",
+        }
+    }
+
+    // Color escrito en sRGB (paletas de los shaders, luces) en el espacio de trabajo
+    pub fn srgb(self, r: f32, g: f32, b: f32) -> Color {
+        self.decode(Color::from_float(r, g, b))
+    }
+
+    // sRGB -> espacio de trabajo, conservando la opacidad
+    pub fn decode(self, c: Color) -> Color {
+        match self {
+            ColorPipeline::Linear => Color { r: srgb_to_linear(c.r), g: srgb_to_linear(c.g), b: srgb_to_linear(c.b), a: c.a },
+            ColorPipeline::Gamma => c,
+        }
+    }
+
+    // Un valor escalar ajustado a ojo sobre valores gamma (p. ej. el ambiente)
+    pub fn decode_value(self, v: f32) -> f32 {
+        match self {
+            ColorPipeline::Linear => srgb_to_linear(v),
+            ColorPipeline::Gamma => v,
+        }
+    }
+
+    // Espacio de trabajo -> 0xRRGGBB, recortando a [0, 1] (sin tone mapping)
+    pub fn encode(self, c: Color) -> u32 {
+        match self {
+            ColorPipeline::Linear => (linear_to_srgb_byte(c.r) << 16) | (linear_to_srgb_byte(c.g) << 8) | linear_to_srgb_byte(c.b),
+            ColorPipeline::Gamma => c.to_hex(),
+        }
+    }
+}
+
+// sRGB -> lineal con la curva exacta por tramos, inversa de la que usa
+// `linear_to_srgb_byte` (así los oscuros ida y vuelta conservan su byte)
+pub fn srgb_to_linear(c: f32) -> f32 {
+    if c <= 0.040_45 { c / 12.92 } else { ((c + 0.055) / 1.055).powf(2.4) }
+}
+
+// lineal [0, 1] -> byte sRGB, con tabla porque se usa en cada píxel del resolve
+pub fn linear_to_srgb_byte(c: f32) -> u32 {
+    const SIZE: usize = 4096;
+    static TABLE: OnceLock<Vec<u8>> = OnceLock::new();
+    let table = TABLE.get_or_init(|| {
+        (0..SIZE)
+            .map(|i| {
+                let l = i as f32 / (SIZE - 1) as f32;
+                let s = if l <= 0.003_130_8 { l * 12.92 } else { 1.055 * l.powf(1.0 / 2.4) - 0.055 };
+                (s * 255.0).round() as u8
+            })
+            .collect()
+    });
+    table[(c.clamp(0.0, 1.0) * (SIZE - 1) as f32).round() as usize] as u32
+}
+
+// Color en punto flotante (1.0 = blanco de pantalla). Los shaders pueden pasar de
+// 1.0 (HDR): el framebuffer guarda el valor tal cual y el tone mapping lo comprime.
 #[derive(Debug, Clone, Copy)]
 pub struct Color {
-    pub r: u8,
-    pub g: u8,
-    pub b: u8,
+    pub r: f32,
+    pub g: f32,
+    pub b: f32,
+    pub a: f32, // opacidad; sólo se usa al mezclar (1.0 = opaco)
 }
 
 impl Color {
     pub fn new(r: u8, g: u8, b: u8) -> Self {
-        Self { r, g, b }
+        Self::from_float(r as f32 / 255.0, g as f32 / 255.0, b as f32 / 255.0)
     }
 
+    // Valores tal cual, sin conversión: colores escritos en sRGB (que se pasan al
+    // espacio de trabajo con `ColorPipeline::decode`) o de overlays ya en pantalla
     pub fn from_float(r: f32, g: f32, b: f32) -> Self {
-        Self {
-            r: (r.clamp(0.0, 1.0) * 255.0) as u8,
-            g: (g.clamp(0.0, 1.0) * 255.0) as u8,
-            b: (b.clamp(0.0, 1.0) * 255.0) as u8,
-        }
+        Self { r: r.max(0.0), g: g.max(0.0), b: b.max(0.0), a: 1.0 }
     }
 
+    // Valor ya lineal (radiancia calculada, p. ej. la dispersión atmosférica)
+    pub fn linear(r: f32, g: f32, b: f32) -> Self {
+        Self::from_float(r, g, b)
+    }
+
+    pub fn from_hex(hex: u32) -> Self {
+        Self::new((hex >> 16) as u8, (hex >> 8) as u8, hex as u8)
+    }
+
+    pub fn with_alpha(self, a: f32) -> Self {
+        Self { a: a.clamp(0.0, 1.0), ..self }
+    }
+
+    // Empaqueta en 0xRRGGBB recortando a [0, 1], sin codificar (overlays; la imagen
+    // de la escena pasa por `ColorPipeline::encode`)
     pub fn to_hex(self) -> u32 {
-        ((self.r as u32) << 16) | ((self.g as u32) << 8) | (self.b as u32)
+        let q = |c: f32| (c.clamp(0.0, 1.0) * 255.0).round() as u32;
+        (q(self.r) << 16) | (q(self.g) << 8) | q(self.b)
     }
 }
 
@@ -29,9 +125,23 @@ impl Add for Color {
     type Output = Color;
     fn add(self, other: Color) -> Color {
         Color {
-            r: self.r.saturating_add(other.r),
-            g: self.g.saturating_add(other.g),
-            b: self.b.saturating_add(other.b),
+            r: self.r + other.r,
+            g: self.g + other.g,
+            b: self.b + other.b,
+            a: self.a,
+        }
+    }
+}
+
+// Modulación por componente (p. ej. color base por color de la luz)
+impl Mul<Color> for Color {
+    type Output = Color;
+    fn mul(self, other: Color) -> Color {
+        Color {
+            r: self.r * other.r,
+            g: self.g * other.g,
+            b: self.b * other.b,
+            a: self.a,
         }
     }
 }
@@ -39,10 +149,32 @@ impl Add for Color {
 impl Mul<f32> for Color {
     type Output = Color;
     fn mul(self, s: f32) -> Color {
+        let s = s.max(0.0);
         Color {
-            r: (self.r as f32 * s).clamp(0.0, 255.0) as u8,
-            g: (self.g as f32 * s).clamp(0.0, 255.0) as u8,
-            b: (self.b as f32 * s).clamp(0.0, 255.0) as u8,
+            r: self.r * s,
+            g: self.g * s,
+            b: self.b * s,
+            a: self.a,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn srgb_bytes_round_trip() {
+        for byte in 0..=255u32 {
+            let linear = srgb_to_linear(byte as f32 / 255.0);
+            assert_eq!(linear_to_srgb_byte(linear), byte, "byte {byte}");
+        }
+    }
+
+    #[test]
+    fn srgb_decode_endpoints() {
+        assert_eq!(srgb_to_linear(0.0), 0.0);
+        assert!((srgb_to_linear(1.0) - 1.0).abs() < 1e-6);
+        assert!((srgb_to_linear(0.5) - 0.214_041).abs() < 1e-5);
+    }
+}
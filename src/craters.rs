@@ -0,0 +1,119 @@
+use nalgebra_glm::Vec3;
+use crate::{rng::Rng, shaders::smoothstep};
+
+// Un cráter sobre la esfera unitaria; `radius` es la cuerda del borde
+#[derive(Debug, Clone, Copy)]
+struct Crater {
+    center: Vec3,
+    radius: f32,
+    depth: f32,
+    // brillo de los rayos de eyección (sólo los cráteres jóvenes los tienen)
+    rays: f32,
+    ray_count: f32,
+    ray_phase: f32,
+    // base tangente para medir el ángulo de los rayos
+    tangent: Vec3,
+    bitangent: Vec3,
+}
+
+// Alcance de los rayos y del manto de eyección en radios del cráter
+const RAY_REACH: f32 = 5.0;
+const EJECTA_REACH: f32 = 1.8;
+// Celdas por eje de la rejilla que reparte los cráteres en [-1, 1]^3
+const GRID: usize = 12;
+
+// Campo de cráteres generado con semilla: lista de cráteres con radios según una
+// ley de potencias (muchos pequeños, pocos grandes) y una rejilla para consultarlos.
+pub struct CraterField {
+    craters: Vec<Crater>,
+    cells: Vec<Vec<u32>>,
+}
+
+impl CraterField {
+    // `count` fija la densidad; los radios van de `min_radius` a `max_radius`
+    // (cuerda sobre la esfera unitaria) con N(>r) ~ r^-size_slope.
+    pub fn generate(seed: u32, count: usize, min_radius: f32, max_radius: f32, size_slope: f32) -> Self {
+        let mut rng = Rng::new(seed);
+        let mut craters = Vec::with_capacity(count);
+        for _ in 0..count {
+            let z = rng.next() * 2.0 - 1.0;
+            let phi = rng.next() * std::f32::consts::TAU;
+            let s = (1.0 - z * z).sqrt();
+            let center = Vec3::new(s * phi.cos(), s * phi.sin(), z);
+
+            // muestreo inverso de la ley de potencias truncada
+            let u = rng.next();
+            let (a, b) = (min_radius.powf(-size_slope), max_radius.powf(-size_slope));
+            let radius = (a + u * (b - a)).powf(-1.0 / size_slope);
+
+            let up = if center.y.abs() < 0.9 { Vec3::new(0.0, 1.0, 0.0) } else { Vec3::new(1.0, 0.0, 0.0) };
+            let tangent = up.cross(&center).normalize();
+            let bitangent = center.cross(&tangent);
+            let young = rng.next() < 0.12;
+            craters.push(Crater {
+                center,
+                radius,
+                depth: radius * (0.35 + 0.25 * rng.next()),
+                rays: if young { 0.5 + 0.5 * rng.next() } else { 0.0 },
+                ray_count: (7.0 + rng.next() * 9.0).floor(),
+                ray_phase: rng.next() * std::f32::consts::TAU,
+                tangent,
+                bitangent,
+            });
+        }
+
+        // cada cráter se guarda en todas las celdas que toca su zona de influencia
+        let mut cells = vec![Vec::new(); GRID * GRID * GRID];
+        for (i, c) in craters.iter().enumerate() {
+            let reach = c.radius * if c.rays > 0.0 { RAY_REACH } else { EJECTA_REACH };
+            let lo = (c.center - Vec3::repeat(reach)).map(cell_coord);
+            let hi = (c.center + Vec3::repeat(reach)).map(cell_coord);
+            for z in lo.z as usize..=hi.z as usize {
+                for y in lo.y as usize..=hi.y as usize {
+                    for x in lo.x as usize..=hi.x as usize {
+                        cells[(z * GRID + y) * GRID + x].push(i as u32);
+                    }
+                }
+            }
+        }
+        Self { craters, cells }
+    }
+
+    // (altura, cambio de albedo) en una dirección del espacio de objeto.
+    // La altura está en las mismas unidades que el radio de los cráteres.
+    pub fn sample(&self, dir: Vec3) -> (f32, f32) {
+        let d = dir.normalize();
+        let c = d.map(cell_coord);
+        let cell = &self.cells[(c.z as usize * GRID + c.y as usize) * GRID + c.x as usize];
+
+        let mut height = 0.0;
+        let mut albedo = 0.0;
+        for &i in cell {
+            let crater = &self.craters[i as usize];
+            let x = (d - crater.center).norm() / crater.radius;
+            if x < 1.0 {
+                // cuenco con fondo plano y borde levantado
+                let bowl = 1.0 - smoothstep(0.3, 1.0, x);
+                height -= crater.depth * bowl;
+                albedo -= 0.08 * bowl;
+            }
+            if x < EJECTA_REACH {
+                let rim = (-((x - 1.0) / 0.2).powi(2)).exp();
+                height += crater.depth * 0.35 * rim;
+                albedo += 0.1 * rim;
+            }
+            if crater.rays > 0.0 && x > 0.8 && x < RAY_REACH {
+                let rel = d - crater.center;
+                let angle = rel.dot(&crater.bitangent).atan2(rel.dot(&crater.tangent));
+                let spoke = ((angle * crater.ray_count + crater.ray_phase).sin() * 0.5 + 0.5).powf(8.0);
+                let fade = 1.0 - smoothstep(1.0, RAY_REACH, x);
+                albedo += 0.25 * crater.rays * spoke * fade * fade;
+            }
+        }
+        (height, albedo)
+    }
+}
+
+fn cell_coord(v: f32) -> f32 {
+    (((v + 1.0) * 0.5 * GRID as f32).floor()).clamp(0.0, GRID as f32 - 1.0)
+}
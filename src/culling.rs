@@ -0,0 +1,159 @@
+use nalgebra_glm::{Mat4, Vec3, Vec4};
+use crate::framebuffer::Framebuffer;
+
+// Planos del frustum extraídos de projection * view (Gribb-Hartmann, z en [-1, 1]).
+// Cada plano es (n, d) con n normalizada; un punto está dentro si n·p + d >= 0.
+pub struct Frustum {
+    planes: [Vec4; 6],
+}
+
+impl Frustum {
+    pub fn from_matrix(m: &Mat4) -> Self {
+        let row = |i: usize| Vec4::new(m[(i, 0)], m[(i, 1)], m[(i, 2)], m[(i, 3)]);
+        let (r0, r1, r2, r3) = (row(0), row(1), row(2), row(3));
+        let mut planes = [r3 + r0, r3 - r0, r3 + r1, r3 - r1, r3 + r2, r3 - r2];
+        for p in planes.iter_mut() {
+            let len = Vec3::new(p.x, p.y, p.z).norm();
+            if len > 0.0 {
+                *p /= len;
+            }
+        }
+        Self { planes }
+    }
+
+    pub fn intersects_sphere(&self, center: Vec3, radius: f32) -> bool {
+        self.planes
+            .iter()
+            .all(|p| p.x * center.x + p.y * center.y + p.z * center.z + p.w >= -radius)
+    }
+}
+
+// Planos cercano y lejano (distancias positivas) de una proyección en perspectiva
+// con z en [-1, 1], leídos de la propia matriz
+pub fn clip_planes(projection: &Mat4) -> (f32, f32) {
+    let (a, b) = (projection[(2, 2)], projection[(2, 3)]);
+    (b / (a - 1.0), b / (a + 1.0))
+}
+
+// Rectángulo en pantalla y profundidad más cercana de una esfera.
+pub struct ScreenBounds {
+    pub min_x: f32,
+    pub min_y: f32,
+    pub max_x: f32,
+    pub max_y: f32,
+    pub nearest_depth: f32,
+}
+
+// Proyecta la caja envolvente (en espacio de vista) de la esfera; el rectángulo
+// resultante contiene siempre a la esfera. None si cruza el plano cercano.
+pub fn sphere_screen_bounds(center: Vec3, radius: f32, view: &Mat4, projection: &Mat4, viewport: &Mat4) -> Option<ScreenBounds> {
+    let c = view * Vec4::new(center.x, center.y, center.z, 1.0);
+    let near_z = c.z + radius; // la cámara mira hacia -z
+    let (near_plane, _) = clip_planes(projection);
+    if near_z >= -near_plane {
+        return None;
+    }
+
+    let mut b = ScreenBounds {
+        min_x: f32::INFINITY,
+        min_y: f32::INFINITY,
+        max_x: f32::NEG_INFINITY,
+        max_y: f32::NEG_INFINITY,
+        nearest_depth: 0.0,
+    };
+    for &dx in &[-radius, radius] {
+        for &dy in &[-radius, radius] {
+            for &dz in &[-radius, radius] {
+                let clip = projection * Vec4::new(c.x + dx, c.y + dy, c.z + dz, 1.0);
+                let ndc = Vec4::new(clip.x / clip.w, clip.y / clip.w, clip.z / clip.w, 1.0);
+                let s = viewport * ndc;
+                b.min_x = b.min_x.min(s.x);
+                b.min_y = b.min_y.min(s.y);
+                b.max_x = b.max_x.max(s.x);
+                b.max_y = b.max_y.max(s.y);
+            }
+        }
+    }
+
+    let clip = projection * Vec4::new(0.0, 0.0, near_z, 1.0);
+    b.nearest_depth = (viewport * Vec4::new(0.0, 0.0, clip.z / clip.w, 1.0)).z;
+    Some(b)
+}
+
+// Pirámide de profundidad máxima construida desde Framebuffer::zbuffer.
+// El nivel k guarda el máximo de bloques de 2^k x 2^k píxeles.
+pub struct HiZ {
+    levels: Vec<(usize, usize, Vec<f32>)>,
+}
+
+impl HiZ {
+    pub fn build(framebuffer: &Framebuffer) -> Self {
+        let mut levels = vec![(framebuffer.width, framebuffer.height, framebuffer.zbuffer.clone())];
+        loop {
+            let (w, h, prev) = levels.last().unwrap();
+            let (w, h) = (*w, *h);
+            if w == 1 && h == 1 {
+                break;
+            }
+            let nw = w.div_ceil(2);
+            let nh = h.div_ceil(2);
+            let mut next = vec![0.0f32; nw * nh];
+            for y in 0..nh {
+                for x in 0..nw {
+                    let mut m = f32::NEG_INFINITY;
+                    for sy in (y * 2)..(y * 2 + 2).min(h) {
+                        for sx in (x * 2)..(x * 2 + 2).min(w) {
+                            m = m.max(prev[sy * w + sx]);
+                        }
+                    }
+                    next[y * nw + x] = m;
+                }
+            }
+            levels.push((nw, nh, next));
+        }
+        Self { levels }
+    }
+
+    // Oculto si en todo el rectángulo lo ya dibujado está más cerca que la esfera.
+    pub fn is_occluded(&self, bounds: &ScreenBounds) -> bool {
+        let (w0, h0, _) = &self.levels[0];
+        let min_x = bounds.min_x.floor().max(0.0) as usize;
+        let min_y = bounds.min_y.floor().max(0.0) as usize;
+        let max_x = bounds.max_x.ceil().min(*w0 as f32 - 1.0);
+        let max_y = bounds.max_y.ceil().min(*h0 as f32 - 1.0);
+        if max_x < min_x as f32 || max_y < min_y as f32 {
+            return false;
+        }
+        let (max_x, max_y) = (max_x as usize, max_y as usize);
+
+        // nivel donde el rectángulo ocupa a lo sumo ~4 texels por lado
+        let span = (max_x - min_x).max(max_y - min_y) + 1;
+        let mut level = 0;
+        while level + 1 < self.levels.len() && (span >> level) > 4 {
+            level += 1;
+        }
+
+        let (w, _, depths) = &self.levels[level];
+        for y in (min_y >> level)..=(max_y >> level) {
+            for x in (min_x >> level)..=(max_x >> level) {
+                if depths[y * w + x] >= bounds.nearest_depth {
+                    return false;
+                }
+            }
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clip_planes_match_the_projection() {
+        let projection = nalgebra_glm::perspective(16.0 / 9.0, 45f32.to_radians(), 0.1, 1000.0);
+        let (near, far) = clip_planes(&projection);
+        assert!((near - 0.1).abs() < 1e-5);
+        assert!((far - 1000.0).abs() < 1.0);
+    }
+}
@@ -0,0 +1,228 @@
+use nalgebra_glm::{Mat4, Vec3};
+use crate::{
+    color::{Color, ColorPipeline},
+    culling::clip_planes,
+    fragment::Fragment,
+    framebuffer::Framebuffer,
+    line::{draw_line, LineStyle},
+    tonemap::{ToneMapper, ToneMapping},
+};
+
+// Cuánto se acercan las aristas del wireframe (en distancia relativa) para ganar
+// la prueba de profundidad a su propia superficie sin atravesar la cara oculta
+const WIRE_BIAS: f32 = 0.005;
+// Fragmentos por píxel que ya se ven blancos en el mapa de sobre-dibujado
+const OVERDRAW_MAX: f32 = 8.0;
+
+// Modos de visualización para depurar la malla y el sombreado
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DebugView {
+    Off,
+    // aristas de los triángulos sobre la imagen normal
+    Wireframe,
+    // normal interpolada en mundo como color (xyz -> rgb)
+    Normals,
+    // distancia a la cámara en grises (cerca = claro), con el rango del cuadro
+    Depth,
+    // fragmentos rasterizados por píxel, pasen o no la profundidad
+    Overdraw,
+    // un color por triángulo
+    FlatTriangles,
+}
+
+impl DebugView {
+    pub fn next(self) -> Self {
+        match self {
+            DebugView::Off => DebugView::Wireframe,
+            DebugView::Wireframe => DebugView::Normals,
+            DebugView::Normals => DebugView::Depth,
+            DebugView::Depth => DebugView::Overdraw,
+            DebugView::Overdraw => DebugView::FlatTriangles,
+            DebugView::FlatTriangles => DebugView::Off,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            DebugView::Off => "no",
+            DebugView::Wireframe => "wireframe",
+            DebugView::Normals => "normales",
+            DebugView::Depth => "profundidad",
+            DebugView::Overdraw => "sobre-dibujado",
+            DebugView::FlatTriangles => "color por triángulo",
+        }
+    }
+
+    // Los modos de color falso sustituyen la imagen: sin fondo ni post-proceso
+    pub fn replaces_image(self) -> bool {
+        !matches!(self, DebugView::Off | DebugView::Wireframe)
+    }
+
+    // Colores que no deben mezclarse: cada capa (nubes, atmósfera) se dibuja opaca
+    pub fn forces_opaque(self) -> bool {
+        matches!(self, DebugView::Normals | DebugView::FlatTriangles)
+    }
+
+    // Sin curva ni exposición, para que los colores de depuración se lean tal cual
+    pub fn tone_mapping(self, scene: ToneMapping) -> ToneMapping {
+        if self.replaces_image() {
+            ToneMapping { operator: ToneMapper::Clamp, exposure: 1.0 }
+        } else {
+            scene
+        }
+    }
+}
+
+// Estado del modo de depuración durante un cuadro: lo que `render` va anotando
+// (triángulos para el wireframe, cuenta de fragmentos) y se compone tras la geometría
+pub struct DebugPass {
+    pub view: DebugView,
+    pipeline: ColorPipeline,
+    // planos cercano y lejano de la proyección del cuadro, para pasar la
+    // profundidad de pantalla a distancia (se fijan en `begin`)
+    near: f32,
+    far: f32,
+    width: usize,
+    triangles: Vec<[Vec3; 3]>,
+    overdraw: Vec<u32>,
+}
+
+impl DebugPass {
+    pub fn new() -> Self {
+        Self { view: DebugView::Off, pipeline: ColorPipeline::Linear, near: 0.0, far: 0.0, width: 0, triangles: Vec::new(), overdraw: Vec::new() }
+    }
+
+    pub fn begin(&mut self, view: DebugView, pipeline: ColorPipeline, projection: &Mat4, framebuffer: &Framebuffer) {
+        self.view = view;
+        self.pipeline = pipeline;
+        (self.near, self.far) = clip_planes(projection);
+        self.width = framebuffer.width;
+        self.triangles.clear();
+        self.overdraw.clear();
+        if view == DebugView::Overdraw {
+            self.overdraw.resize(framebuffer.width * framebuffer.height, 0);
+        }
+    }
+
+    // Triángulo en coordenadas de pantalla (x, y, profundidad) ya rasterizado
+    pub fn record_triangle(&mut self, triangle: [Vec3; 3]) {
+        if self.view == DebugView::Wireframe {
+            self.triangles.push(triangle);
+        }
+    }
+
+    // Color que sustituye al del shader, si el modo lo pide. `index` identifica el
+    // triángulo dentro de su malla.
+    pub fn shade(&mut self, fragment: &Fragment, index: usize) -> Option<Color> {
+        match self.view {
+            DebugView::Normals => {
+                let n = fragment.normal * 0.5 + Vec3::new(0.5, 0.5, 0.5);
+                Some(self.pipeline.srgb(n.x, n.y, n.z))
+            }
+            DebugView::FlatTriangles => Some(self.pipeline.decode(triangle_color(index))),
+            DebugView::Overdraw => {
+                let (x, y) = (fragment.position.x as usize, fragment.position.y as usize);
+                if let Some(count) = self.overdraw.get_mut(y * self.width + x) {
+                    *count += 1;
+                }
+                Some(Color::linear(0.0, 0.0, 0.0))
+            }
+            _ => None,
+        }
+    }
+
+    // Tras `resolve_samples`: dibuja las aristas o sustituye la imagen por el mapa
+    pub fn compose(&self, framebuffer: &mut Framebuffer, style: LineStyle) {
+        match self.view {
+            DebugView::Wireframe => self.draw_wireframe(framebuffer, style),
+            DebugView::Depth => self.visualize_depth(framebuffer),
+            DebugView::Overdraw => {
+                for (out, &count) in framebuffer.color.iter_mut().zip(&self.overdraw) {
+                    *out = self.pipeline.decode(heat(count as f32 / OVERDRAW_MAX));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn draw_wireframe(&self, framebuffer: &mut Framebuffer, style: LineStyle) {
+        let color = self.pipeline.srgb(0.2, 1.0, 0.4);
+        for triangle in &self.triangles {
+            // triángulos con algún vértice detrás de la cámara no tienen aristas válidas
+            if triangle.iter().any(|v| v.z.abs() > 1.0) {
+                continue;
+            }
+            for i in 0..3 {
+                let (a, b) = (self.pull_forward(triangle[i]), self.pull_forward(triangle[(i + 1) % 3]));
+                draw_line(framebuffer, a, b, color, 0.8, style);
+            }
+        }
+    }
+
+    // Profundidad de pantalla (z NDC) <-> distancia a la cámara
+    fn linear_depth(&self, z: f32) -> f32 {
+        let (near, far) = (self.near, self.far);
+        2.0 * near * far / (far + near - z * (far - near))
+    }
+
+    fn ndc_depth(&self, d: f32) -> f32 {
+        let (near, far) = (self.near, self.far);
+        (far + near - 2.0 * near * far / d) / (far - near)
+    }
+
+    fn pull_forward(&self, v: Vec3) -> Vec3 {
+        Vec3::new(v.x, v.y, self.ndc_depth(self.linear_depth(v.z) * (1.0 - WIRE_BIAS)))
+    }
+
+    // Grises por distancia, normalizados entre lo más cercano y lo más lejano del
+    // cuadro; el fondo (sin profundidad) queda negro
+    fn visualize_depth(&self, framebuffer: &mut Framebuffer) {
+        let (near, far) = framebuffer
+            .zbuffer
+            .iter()
+            .filter(|z| z.is_finite())
+            .map(|&z| self.linear_depth(z))
+            .fold((f32::INFINITY, 0.0f32), |(lo, hi), d| (lo.min(d), hi.max(d)));
+        let span = (far - near).max(1e-3);
+        for (out, &z) in framebuffer.color.iter_mut().zip(&framebuffer.zbuffer) {
+            *out = if z.is_finite() {
+                let g = 1.0 - 0.85 * (self.linear_depth(z) - near) / span;
+                self.pipeline.srgb(g, g, g)
+            } else {
+                Color::linear(0.0, 0.0, 0.0)
+            };
+        }
+    }
+}
+
+// Rampa negro -> azul -> verde -> amarillo -> rojo -> blanco para t en 0..1
+fn heat(t: f32) -> Color {
+    const RAMP: [(f32, f32, f32); 6] = [(0.0, 0.0, 0.0), (0.0, 0.2, 1.0), (0.0, 0.9, 0.3), (1.0, 0.9, 0.0), (1.0, 0.1, 0.0), (1.0, 1.0, 1.0)];
+    let x = t.clamp(0.0, 1.0) * (RAMP.len() - 1) as f32;
+    let i = (x as usize).min(RAMP.len() - 2);
+    let f = x - i as f32;
+    let (a, b) = (RAMP[i], RAMP[i + 1]);
+    Color::from_float(a.0 + (b.0 - a.0) * f, a.1 + (b.1 - a.1) * f, a.2 + (b.2 - a.2) * f)
+}
+
+// Color saturado y estable por índice (hash entero -> tono)
+fn triangle_color(index: usize) -> Color {
+    let mut h = index as u32;
+    h = (h ^ 61) ^ (h >> 16);
+    h = h.wrapping_mul(9);
+    h ^= h >> 4;
+    h = h.wrapping_mul(0x27d4_eb2d);
+    h ^= h >> 15;
+    let hue = (h & 0xffff) as f32 / 65536.0 * 6.0;
+    let x = 1.0 - (hue % 2.0 - 1.0).abs();
+    let (r, g, b) = match hue as u32 {
+        0 => (1.0, x, 0.0),
+        1 => (x, 1.0, 0.0),
+        2 => (0.0, 1.0, x),
+        3 => (0.0, x, 1.0),
+        4 => (x, 0.0, 1.0),
+        _ => (1.0, 0.0, x),
+    };
+    let shade = 0.55 + 0.45 * ((h >> 16) & 0xff) as f32 / 255.0;
+    Color::from_float(r * shade, g * shade, b * shade)
+}
@@ -1,18 +1,30 @@
 use nalgebra_glm::{Vec2, Vec3};
-use crate::color::Color;
+use crate::framebuffer::MAX_SAMPLES;
 
 #[derive(Clone, Debug)]
 pub struct Fragment {
     pub position: Vec2, // screen
     pub depth: f32,
     pub normal: Vec3, // interpolated, normalized
-    pub intensity: f32,
     pub vertex_position: Vec3, // original object position interpolated
-    pub color: Color,
+    pub world_position: Vec3,
+    pub tex_coords: Vec2,
+    // MSAA: bit i = la muestra i del píxel cae dentro del triángulo, con su profundidad
+    pub coverage: u32,
+    pub sample_depths: [f32; MAX_SAMPLES],
 }
 
 impl Fragment {
-    pub fn new(position: Vec2, depth: f32, normal: Vec3, intensity: f32, vertex_position: Vec3) -> Self {
-        Self { position, depth, normal, intensity, vertex_position, color: Color::new(0,0,0) }
+    pub fn new(position: Vec2, depth: f32, normal: Vec3, vertex_position: Vec3, world_position: Vec3, tex_coords: Vec2) -> Self {
+        Self {
+            position,
+            depth,
+            normal,
+            vertex_position,
+            world_position,
+            tex_coords,
+            coverage: 1,
+            sample_depths: [depth; MAX_SAMPLES],
+        }
     }
 }
@@ -1,9 +1,41 @@
+use crate::{color::{Color, ColorPipeline}, tonemap::ToneMapping};
+
+// Cómo se combina un fragmento con lo que ya hay en el buffer
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BlendMode {
+    // reemplaza color y escribe profundidad (geometría opaca)
+    Replace,
+    // suma el color sin escribir profundidad (luz dispersada, brillos)
+    Additive,
+    // mezcla "over" con la opacidad actual, sin escribir profundidad
+    Alpha,
+}
+
+// Muestras por píxel como máximo (MSAA 4x) y sus posiciones dentro del píxel:
+// rejilla rotada, mejor en bordes casi horizontales/verticales que una 2x2 recta
+pub const MAX_SAMPLES: usize = 4;
+pub const SINGLE_SAMPLE: [(f32, f32); 1] = [(0.5, 0.5)];
+pub const MSAA_4X: [(f32, f32); MAX_SAMPLES] = [(0.375, 0.125), (0.875, 0.375), (0.125, 0.625), (0.625, 0.875)];
+
+// `color` es el buffer HDR en flotante donde dibujan los shaders; `buffer` es la
+// imagen de 8 bits para minifb/capturas, que sale de `resolve` (tone mapping).
+// Con MSAA la geometría se dibuja en `sample_color`/`sample_depth` (N por píxel)
+// y `resolve_samples` promedia a `color`; `zbuffer` guarda entonces la muestra más
+// lejana de cada píxel, que es lo que necesitan el Hi-Z y la oclusión del halo.
 pub struct Framebuffer {
     pub width: usize,
     pub height: usize,
+    pub color: Vec<Color>,
     pub buffer: Vec<u32>,
     pub zbuffer: Vec<f32>,
-    current_color: u32,
+    samples: usize,
+    // píxeles de render por píxel de la imagen final, en cada eje (capturas con
+    // supersampling); lo que se dibuja a tamaño de píxel debe escalar con esto
+    pub supersample: usize,
+    sample_color: Vec<Color>,
+    sample_depth: Vec<f32>,
+    current_color: Color,
+    blend_mode: BlendMode,
 }
 
 impl Framebuffer {
@@ -11,21 +43,47 @@ impl Framebuffer {
         Self {
             width,
             height,
+            color: vec![Color::from_float(0.0, 0.0, 0.0); width * height],
             buffer: vec![0; width * height],
             zbuffer: vec![f32::INFINITY; width * height],
-            current_color: 0xFFFFFF,
+            samples: 1,
+            supersample: 1,
+            sample_color: Vec::new(),
+            sample_depth: Vec::new(),
+            current_color: Color::from_float(1.0, 1.0, 1.0),
+            blend_mode: BlendMode::Replace,
         }
     }
 
-    pub fn clear(&mut self, color: u32) {
-        self.buffer.fill(color);
+    pub fn clear(&mut self, color: Color) {
+        self.color.fill(color);
         self.zbuffer.fill(f32::INFINITY);
+        self.sample_color.fill(color);
+        self.sample_depth.fill(f32::INFINITY);
+    }
+
+    // 1 = sin MSAA; hasta MAX_SAMPLES. Borra los buffers de muestras.
+    pub fn set_samples(&mut self, samples: usize) {
+        self.samples = samples.clamp(1, MAX_SAMPLES);
+        let n = if self.samples > 1 { self.width * self.height * self.samples } else { 0 };
+        self.sample_color = vec![Color::linear(0.0, 0.0, 0.0); n];
+        self.sample_depth = vec![f32::INFINITY; n];
+    }
+
+    // Posiciones de muestra que debe usar el rasterizador
+    pub fn sample_pattern(&self) -> &'static [(f32, f32)] {
+        if self.samples > 1 { &MSAA_4X[..self.samples] } else { &SINGLE_SAMPLE }
     }
 
-    pub fn set_current_color(&mut self, color: u32) {
+    // Color y opacidad (alpha) con los que escribe `point`
+    pub fn set_current_color(&mut self, color: Color) {
         self.current_color = color;
     }
 
+    pub fn set_blend_mode(&mut self, mode: BlendMode) {
+        self.blend_mode = mode;
+    }
+
     #[inline]
     pub fn point(&mut self, x: i32, y: i32, depth: f32) {
         if x < 0 || y < 0 || x as usize >= self.width || y as usize >= self.height {
@@ -33,8 +91,145 @@ impl Framebuffer {
         }
         let idx = y as usize * self.width + x as usize;
         if depth < self.zbuffer[idx] {
-            self.zbuffer[idx] = depth;
-            self.buffer[idx] = self.current_color;
+            let c = self.current_color;
+            match self.blend_mode {
+                BlendMode::Replace => {
+                    self.zbuffer[idx] = depth;
+                    self.color[idx] = c;
+                }
+                BlendMode::Additive => {
+                    self.color[idx] = self.color[idx] + c;
+                }
+                BlendMode::Alpha => {
+                    self.color[idx] = self.color[idx] * (1.0 - c.a) + c * c.a;
+                }
+            }
+        }
+    }
+
+    // Escribe las muestras cubiertas (`coverage`) de un píxel con el color actual,
+    // cada una con su profundidad. El color se calculó una vez para todo el píxel.
+    pub fn point_samples(&mut self, x: i32, y: i32, coverage: u32, depths: &[f32]) {
+        if self.samples == 1 {
+            if coverage & 1 != 0 {
+                self.point(x, y, depths[0]);
+            }
+            return;
+        }
+        if x < 0 || y < 0 || x as usize >= self.width || y as usize >= self.height {
+            return;
+        }
+        let idx = y as usize * self.width + x as usize;
+        let base = idx * self.samples;
+        let c = self.current_color;
+        let mut wrote_depth = false;
+        for (s, &depth) in depths.iter().enumerate().take(self.samples) {
+            let i = base + s;
+            if coverage & (1 << s) == 0 || depth >= self.sample_depth[i] {
+                continue;
+            }
+            match self.blend_mode {
+                BlendMode::Replace => {
+                    self.sample_depth[i] = depth;
+                    self.sample_color[i] = c;
+                    wrote_depth = true;
+                }
+                BlendMode::Additive => {
+                    self.sample_color[i] = self.sample_color[i] + c;
+                }
+                BlendMode::Alpha => {
+                    self.sample_color[i] = self.sample_color[i] * (1.0 - c.a) + c * c.a;
+                }
+            }
+        }
+        if wrote_depth {
+            self.zbuffer[idx] = self.sample_depth[base..base + self.samples].iter().copied().fold(f32::NEG_INFINITY, f32::max);
+        }
+    }
+
+    // Promedia las muestras a `color` (tras dibujar la geometría, antes del post-proceso)
+    pub fn resolve_samples(&mut self) {
+        if self.samples == 1 {
+            return;
+        }
+        let weight = 1.0 / self.samples as f32;
+        for (out, samples) in self.color.iter_mut().zip(self.sample_color.chunks_exact(self.samples)) {
+            let sum = samples.iter().fold(Color::linear(0.0, 0.0, 0.0), |acc, &c| acc + c);
+            *out = sum * weight;
+        }
+    }
+
+    // Suma al fondo antes de dibujar la geometría (cielo): va también a todas las
+    // muestras MSAA para que `resolve_samples` no lo pise
+    #[inline]
+    pub fn add_background(&mut self, x: i32, y: i32, color: Color) {
+        if x < 0 || y < 0 || x as usize >= self.width || y as usize >= self.height {
+            return;
+        }
+        let idx = y as usize * self.width + x as usize;
+        self.color[idx] = self.color[idx] + color;
+        if self.samples > 1 {
+            for c in &mut self.sample_color[idx * self.samples..(idx + 1) * self.samples] {
+                *c = *c + color;
+            }
+        }
+    }
+
+    // Suma un color sin prueba de profundidad (brillos de post-proceso)
+    #[inline]
+    pub fn add_pixel(&mut self, x: i32, y: i32, color: Color) {
+        if x < 0 || y < 0 || x as usize >= self.width || y as usize >= self.height {
+            return;
+        }
+        let idx = y as usize * self.width + x as usize;
+        self.color[idx] = self.color[idx] + color;
+    }
+
+    // Mezcla `color` con opacidad `alpha` si pasa la prueba de profundidad, sin
+    // escribirla (líneas sobre la escena ya resuelta, antes del post-proceso)
+    #[inline]
+    pub fn plot(&mut self, x: i32, y: i32, depth: f32, color: Color, alpha: f32) {
+        if x < 0 || y < 0 || x as usize >= self.width || y as usize >= self.height || alpha <= 0.0 {
+            return;
+        }
+        let idx = y as usize * self.width + x as usize;
+        if depth < self.zbuffer[idx] {
+            let alpha = alpha.min(1.0);
+            self.color[idx] = self.color[idx] * (1.0 - alpha) + color * alpha;
+        }
+    }
+
+    // Pasa el HDR a `buffer` con el tone mapping dado, codificando según el pipeline
+    pub fn resolve(&mut self, tone_mapping: &ToneMapping, pipeline: ColorPipeline) {
+        for (out, c) in self.buffer.iter_mut().zip(&self.color) {
+            *out = pipeline.encode(tone_mapping.apply(*c));
+        }
+    }
+
+    // Escribe el color actual en la imagen ya resuelta, sin profundidad ni tone
+    // mapping (overlays de depuración, después de `resolve`)
+    #[inline]
+    pub fn set_pixel(&mut self, x: i32, y: i32) {
+        if x < 0 || y < 0 || x as usize >= self.width || y as usize >= self.height {
+            return;
+        }
+        let idx = y as usize * self.width + x as usize;
+        self.buffer[idx] = self.current_color.to_hex();
+    }
+
+    // Oscurece un rectángulo de la imagen resuelta (fondo de paneles de texto);
+    // `amount` 0 = nada, 1 = negro
+    pub fn dim_rect(&mut self, x: i32, y: i32, width: i32, height: i32, amount: f32) {
+        let keep = (1.0 - amount).clamp(0.0, 1.0);
+        let x0 = x.clamp(0, self.width as i32) as usize;
+        let y0 = y.clamp(0, self.height as i32) as usize;
+        let x1 = (x + width).clamp(0, self.width as i32) as usize;
+        let y1 = (y + height).clamp(0, self.height as i32) as usize;
+        for row in y0..y1 {
+            for px in &mut self.buffer[row * self.width + x0..row * self.width + x1] {
+                let channel = |shift: u32| ((((*px >> shift) & 0xff) as f32 * keep) as u32) << shift;
+                *px = channel(16) | channel(8) | channel(0);
+            }
         }
     }
 }
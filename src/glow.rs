@@ -0,0 +1,130 @@
+use nalgebra_glm::{Mat4, Vec2, Vec3};
+use crate::{color::{Color, ColorPipeline}, framebuffer::Framebuffer, lod::{project_point, projected_radius}, shaders::smoothstep};
+
+// Hasta dónde llega el halo, en radios del disco proyectado
+const CORONA_REACH: f32 = 7.0;
+// Destellos de lente: (posición sobre el eje estrella -> centro, tamaño en radios, color)
+const GHOSTS: [(f32, f32, (f32, f32, f32)); 6] = [
+    (0.45, 0.30, (0.9, 0.6, 0.3)),
+    (0.75, 0.18, (0.5, 0.9, 0.6)),
+    (1.10, 0.55, (0.4, 0.5, 1.0)),
+    (1.35, 0.12, (1.0, 0.8, 0.5)),
+    (1.70, 0.80, (0.6, 0.4, 0.9)),
+    (2.10, 0.35, (0.4, 0.8, 1.0)),
+];
+
+// Estrella proyectada a pantalla para los efectos de brillo (post-proceso).
+// `radius` va por eje porque la proyección no es isotrópica en x/y.
+pub struct StarGlow {
+    pub center: Vec2,
+    pub radius: Vec2,
+    // profundidad del punto de la estrella más cercano a la cámara
+    pub depth: f32,
+    pub color: Color,
+}
+
+impl StarGlow {
+    // None si la estrella queda detrás de la cámara
+    pub fn project(position: Vec3, radius: f32, color: Color, eye: Vec3, view: &Mat4, projection: &Mat4, viewport: &Mat4) -> Option<Self> {
+        let center = project_point(position, view, projection, viewport)?;
+        let front = project_point(position + (eye - position).normalize() * radius, view, projection, viewport)?;
+        // viewport[(1, 1)] = -alto / 2
+        let ry = projected_radius(position, radius, view, projection, viewport[(1, 1)].abs() * 2.0);
+        let rx = ry * projection[(0, 0)] / projection[(1, 1)];
+        Some(Self { center: Vec2::new(center.x, center.y), radius: Vec2::new(rx, ry), depth: front.z, color })
+    }
+
+    // Fracción del disco no tapada por cuerpos más cercanos (muestras en rejilla)
+    pub fn visible_fraction(&self, framebuffer: &Framebuffer) -> f32 {
+        const N: i32 = 8;
+        let mut visible = 0;
+        let mut total = 0;
+        for j in -N..=N {
+            for i in -N..=N {
+                let (u, v) = (i as f32 / N as f32, j as f32 / N as f32);
+                if u * u + v * v > 1.0 {
+                    continue;
+                }
+                total += 1;
+                let x = (self.center.x + u * self.radius.x) as i32;
+                let y = (self.center.y + v * self.radius.y) as i32;
+                // fuera de pantalla cuenta como visible: sólo tapan los cuerpos
+                if !self.occluded(framebuffer, x, y) {
+                    visible += 1;
+                }
+            }
+        }
+        visible as f32 / total.max(1) as f32
+    }
+
+    // Halo radial alrededor del disco; no se dibuja sobre cuerpos más cercanos
+    pub fn draw_corona(&self, framebuffer: &mut Framebuffer, intensity: f32, visibility: f32) {
+        let strength = intensity * visibility;
+        if strength <= 0.0 {
+            return;
+        }
+        let reach = self.radius * CORONA_REACH;
+        let (x0, x1) = ((self.center.x - reach.x).max(0.0) as i32, (self.center.x + reach.x).min(framebuffer.width as f32 - 1.0) as i32);
+        let (y0, y1) = ((self.center.y - reach.y).max(0.0) as i32, (self.center.y + reach.y).min(framebuffer.height as f32 - 1.0) as i32);
+        for y in y0..=y1 {
+            for x in x0..=x1 {
+                let dx = (x as f32 - self.center.x) / self.radius.x;
+                let dy = (y as f32 - self.center.y) / self.radius.y;
+                let d = (dx * dx + dy * dy).sqrt();
+                // empieza algo dentro del disco: el contorno proyectado no es una elipse exacta
+                if !(0.9..CORONA_REACH).contains(&d) || self.occluded(framebuffer, x, y) {
+                    continue;
+                }
+                let fade_in = smoothstep(0.9, 1.0, d);
+                let d = d.max(1.0);
+                // núcleo brillante pegado al borde + cola suave que se apaga en el alcance
+                let angle = dy.atan2(dx);
+                let streamers = 1.0 + 0.08 * ((angle * 7.0).sin() + (angle * 13.0 + 1.3).sin());
+                let g = (0.6 * (-(d - 1.0) * 2.5).exp() + 0.3 / (d * d))
+                    * streamers
+                    * fade_in
+                    * (1.0 - smoothstep(CORONA_REACH * 0.5, CORONA_REACH, d));
+                framebuffer.add_pixel(x, y, self.color * (g * strength));
+            }
+        }
+    }
+
+    // Destellos de lente sobre el eje que va de la estrella al centro de la pantalla.
+    // Son reflejos dentro de la "lente", así que se dibujan encima de todo.
+    pub fn draw_flare(&self, framebuffer: &mut Framebuffer, visibility: f32, pipeline: ColorPipeline) {
+        let (w, h) = (framebuffer.width as f32, framebuffer.height as f32);
+        if visibility <= 0.0 || self.center.x < 0.0 || self.center.y < 0.0 || self.center.x >= w || self.center.y >= h {
+            return;
+        }
+        let screen_center = Vec2::new(w * 0.5, h * 0.5);
+        let axis = screen_center - self.center;
+        // con la estrella centrada los reflejos se amontonan sobre ella: se apagan
+        let strength = visibility * smoothstep(0.0, 0.25, axis.norm() / h);
+        if strength <= 0.0 {
+            return;
+        }
+        for (t, size, (r, g, b)) in GHOSTS {
+            let p = self.center + axis * t;
+            let radius = (self.radius.y * size).max(4.0);
+            let tint = pipeline.srgb(r, g, b);
+            for y in (p.y - radius) as i32..=(p.y + radius) as i32 {
+                for x in (p.x - radius) as i32..=(p.x + radius) as i32 {
+                    let d = ((x as f32 - p.x).powi(2) + (y as f32 - p.y).powi(2)).sqrt() / radius;
+                    if d > 1.0 {
+                        continue;
+                    }
+                    // disco tenue con el borde un poco más marcado
+                    let a = (0.08 + 0.1 * smoothstep(0.6, 0.95, d)) * (1.0 - smoothstep(0.95, 1.0, d));
+                    framebuffer.add_pixel(x, y, tint * (a * strength));
+                }
+            }
+        }
+    }
+
+    fn occluded(&self, framebuffer: &Framebuffer, x: i32, y: i32) -> bool {
+        if x < 0 || y < 0 || x as usize >= framebuffer.width || y as usize >= framebuffer.height {
+            return false;
+        }
+        framebuffer.zbuffer[y as usize * framebuffer.width + x as usize] < self.depth
+    }
+}
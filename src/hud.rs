@@ -0,0 +1,94 @@
+use std::time::Instant;
+use crate::{color::Color, framebuffer::Framebuffer, text::{draw_text, text_width, LINE_HEIGHT}};
+
+// Escala de la fuente y margen de los paneles, en píxeles de pantalla
+const SCALE: i32 = 2;
+const MARGIN: i32 = 8;
+// Peso del último cuadro en la media de tiempos (suaviza la lectura de FPS)
+const SMOOTHING: f32 = 0.1;
+
+// Atajos de teclado, como en el README
+const HELP: &str = "\
+0/1/2/3  todo / estrella / rocoso / gaseoso
+O  órbitas      Q  estelas    W  líneas AA
+L  LOD          C  culling    D  depuración
+H  sombras      M  mapa de sombras   [ ]  sesgo
+P  PCF          A  atmósferas  K  nubes
+G  halo         F  destellos   B  bloom
+, .  umbral bloom    ; '  intensidad bloom
+T  tone mapping      - =  exposición
+N  estrellas    V  banda      U  cielo PNG
+X  antialiasing      E  luz lineal
+R  grabar       S  captura
+I  HUD          F1  ayuda     ESC  salir";
+
+// Tiempo real entre cuadros, medido con reloj de pared y suavizado
+pub struct FrameTimer {
+    last: Instant,
+    frame_ms: f32,
+}
+
+impl FrameTimer {
+    pub fn new() -> Self {
+        Self { last: Instant::now(), frame_ms: 16.0 }
+    }
+
+    // Llamar una vez por cuadro
+    pub fn tick(&mut self) {
+        let now = Instant::now();
+        let dt = now.duration_since(self.last).as_secs_f32() * 1000.0;
+        self.last = now;
+        self.frame_ms += (dt - self.frame_ms) * SMOOTHING;
+    }
+
+    pub fn frame_ms(&self) -> f32 {
+        self.frame_ms
+    }
+
+    pub fn fps(&self) -> f32 {
+        1000.0 / self.frame_ms.max(1e-3)
+    }
+}
+
+// Lo que muestra el HUD en un cuadro
+pub struct HudInfo<'a> {
+    pub fps: f32,
+    pub frame_ms: f32,
+    pub focus: &'a str,
+    // tiempo simulado por segundo real (el reloj avanza un paso fijo por cuadro)
+    pub sim_speed: f32,
+    pub orbits: bool,
+    pub render: String,
+    pub recording: bool,
+}
+
+// Panel de estado arriba a la izquierda y, con `help`, la lista de atajos debajo
+pub fn draw_hud(framebuffer: &mut Framebuffer, info: &HudInfo, help: bool) {
+    let mut status = format!(
+        "FPS {:.1} ({:.1} ms)\nFoco: {}\nSimulación: x{:.2}  órbitas {}\n{}",
+        info.fps,
+        info.frame_ms,
+        info.focus,
+        info.sim_speed,
+        if info.orbits { "sí" } else { "no" },
+        info.render,
+    );
+    if info.recording {
+        status.push_str("\n* grabando");
+    }
+    status.push_str(if help { "\nF1: ocultar ayuda" } else { "\nF1: ayuda" });
+    let bottom = draw_panel(framebuffer, MARGIN, MARGIN, &status, Color::from_float(1.0, 1.0, 1.0));
+    if help {
+        draw_panel(framebuffer, MARGIN, bottom + MARGIN, HELP, Color::from_float(1.0, 0.9, 0.6));
+    }
+}
+
+// Texto sobre un fondo oscurecido; devuelve el borde inferior del panel
+fn draw_panel(framebuffer: &mut Framebuffer, x: i32, y: i32, text: &str, color: Color) -> i32 {
+    let pad = 2 * SCALE;
+    let lines = text.lines().count() as i32;
+    let (width, height) = (text_width(text, SCALE) + 2 * pad, lines * LINE_HEIGHT * SCALE + 2 * pad);
+    framebuffer.dim_rect(x, y, width, height, 0.6);
+    draw_text(framebuffer, x + pad, y + pad, text, color, SCALE);
+    y + height
+}
@@ -0,0 +1,58 @@
+use nalgebra_glm::Vec3;
+use std::collections::HashMap;
+use crate::vertex::Vertex;
+
+// Build an icosphere centered at origin by subdividing an icosahedron.
+// Each subdivision splits every face in 4 (20 * 4^n triangles).
+// Returns a vertex array where every 3 vertices form a triangle.
+pub fn build_icosphere(radius: f32, subdivisions: u32) -> Vec<Vertex> {
+    let t = (1.0 + 5f32.sqrt()) * 0.5;
+    let mut points: Vec<Vec3> = [
+        (-1.0, t, 0.0), (1.0, t, 0.0), (-1.0, -t, 0.0), (1.0, -t, 0.0),
+        (0.0, -1.0, t), (0.0, 1.0, t), (0.0, -1.0, -t), (0.0, 1.0, -t),
+        (t, 0.0, -1.0), (t, 0.0, 1.0), (-t, 0.0, -1.0), (-t, 0.0, 1.0),
+    ]
+    .iter()
+    .map(|&(x, y, z)| Vec3::new(x, y, z).normalize())
+    .collect();
+
+    let mut faces: Vec<[usize; 3]> = vec![
+        [0, 11, 5], [0, 5, 1], [0, 1, 7], [0, 7, 10], [0, 10, 11],
+        [1, 5, 9], [5, 11, 4], [11, 10, 2], [10, 7, 6], [7, 1, 8],
+        [3, 9, 4], [3, 4, 2], [3, 2, 6], [3, 6, 8], [3, 8, 9],
+        [4, 9, 5], [2, 4, 11], [6, 2, 10], [8, 6, 7], [9, 8, 1],
+    ];
+
+    for _ in 0..subdivisions {
+        // midpoints compartidos entre caras vecinas para no duplicar vértices
+        let mut midpoints: HashMap<(usize, usize), usize> = HashMap::new();
+        let mut midpoint = |a: usize, b: usize, points: &mut Vec<Vec3>| -> usize {
+            let key = if a < b { (a, b) } else { (b, a) };
+            *midpoints.entry(key).or_insert_with(|| {
+                points.push(((points[a] + points[b]) * 0.5).normalize());
+                points.len() - 1
+            })
+        };
+
+        let mut next = Vec::with_capacity(faces.len() * 4);
+        for &[a, b, c] in &faces {
+            let ab = midpoint(a, b, &mut points);
+            let bc = midpoint(b, c, &mut points);
+            let ca = midpoint(c, a, &mut points);
+            next.push([a, ab, ca]);
+            next.push([b, bc, ab]);
+            next.push([c, ca, bc]);
+            next.push([ab, bc, ca]);
+        }
+        faces = next;
+    }
+
+    let mut verts = Vec::with_capacity(faces.len() * 3);
+    for face in &faces {
+        for &i in face {
+            // en una esfera la normal es la dirección del punto
+            verts.push(Vertex::new(points[i] * radius, points[i]));
+        }
+    }
+    verts
+}
@@ -0,0 +1,101 @@
+use nalgebra_glm::Vec3;
+use crate::{color::Color, shadow::light_visibility, Uniforms};
+
+// Luz puntual en espacio de mundo. La atenuación es intensity / (1 + falloff * d²).
+// `radius` es el tamaño de la fuente; da la penumbra de las sombras (0 = duras).
+#[derive(Debug, Clone, Copy)]
+pub struct Light {
+    pub position: Vec3,
+    pub radius: f32,
+    pub color: Color,
+    pub intensity: f32,
+    pub falloff: f32,
+}
+
+impl Light {
+    pub fn point(position: Vec3, radius: f32, color: Color, intensity: f32, falloff: f32) -> Self {
+        Self { position, radius, color, intensity, falloff }
+    }
+
+    // Dirección (normalizada) desde `p` hacia la luz y atenuación a esa distancia
+    pub fn direction_from(&self, p: Vec3) -> (Vec3, f32) {
+        let to_light = self.position - p;
+        let d = to_light.norm().max(1e-4);
+        (to_light / d, self.intensity / (1.0 + self.falloff * d * d))
+    }
+}
+
+// Modelo del término especular
+#[derive(Debug, Clone, Copy)]
+pub enum Specular {
+    None,
+    BlinnPhong { shininess: f32 },
+    // microfacetas GGX (Cook-Torrance) con Fresnel de Schlick
+    Ggx { roughness: f32, f0: f32 },
+}
+
+// Respuesta de una superficie a la luz; `specular` escala el brillo especular
+#[derive(Debug, Clone, Copy)]
+pub struct Material {
+    pub ambient: f32,
+    pub diffuse: f32,
+    pub specular: f32,
+    pub model: Specular,
+}
+
+impl Material {
+    pub const fn matte() -> Self {
+        Self { ambient: 0.2, diffuse: 0.8, specular: 0.0, model: Specular::None }
+    }
+}
+
+// Difuso + especular en espacio de mundo sumando cada luz de los uniforms.
+// `spec_mask` (0..1) limita el especular a zonas del shader (p. ej. sólo agua).
+pub fn shade(base: Color, n: Vec3, p: Vec3, uniforms: &Uniforms, material: &Material, spec_mask: f32, two_sided: bool) -> Color {
+    let v = (uniforms.camera_position - p).normalize();
+    let n = if two_sided && n.dot(&v) < 0.0 { -n } else { n };
+    // el ambiente se ajustó a ojo sobre valores gamma: se pasa a lineal como un color
+    let ambient = uniforms.pipeline.decode_value(material.ambient);
+    let mut col = base * ambient;
+    for (i, light) in uniforms.lights.iter().enumerate() {
+        let (l, atten) = light.direction_from(p);
+        let ndl = if two_sided { n.dot(&l).abs() } else { n.dot(&l).max(0.0) };
+        if ndl <= 0.0 {
+            continue;
+        }
+        let mut visibility = light_visibility(p, light, uniforms.occluders);
+        if let Some(shadow_map) = uniforms.shadow_map.filter(|sm| sm.light_index == i) {
+            visibility = visibility.min(shadow_map.visibility(p, ndl));
+        }
+        let atten = atten * visibility;
+        if atten <= 0.0 {
+            continue;
+        }
+        col = col + (base * light.color) * (ndl * atten * material.diffuse);
+
+        let spec = specular_term(&material.model, n, l, v, ndl) * material.specular * spec_mask;
+        if spec > 0.0 {
+            col = col + light.color * (spec * atten);
+        }
+    }
+    col
+}
+
+fn specular_term(model: &Specular, n: Vec3, l: Vec3, v: Vec3, ndl: f32) -> f32 {
+    let h = (l + v).normalize();
+    let ndh = n.dot(&h).max(0.0);
+    match *model {
+        Specular::None => 0.0,
+        Specular::BlinnPhong { shininess } => ndh.powf(shininess) * ndl,
+        Specular::Ggx { roughness, f0 } => {
+            let ndv = n.dot(&v).max(1e-4);
+            let a = roughness * roughness;
+            let a2 = a * a;
+            let d = a2 / (std::f32::consts::PI * (ndh * ndh * (a2 - 1.0) + 1.0).powi(2));
+            let k = (roughness + 1.0).powi(2) / 8.0;
+            let g = (ndv / (ndv * (1.0 - k) + k)) * (ndl / (ndl * (1.0 - k) + k));
+            let f = f0 + (1.0 - f0) * (1.0 - v.dot(&h).max(0.0)).powi(5);
+            d * g * f / (4.0 * ndv * ndl) * ndl
+        }
+    }
+}
@@ -0,0 +1,165 @@
+use nalgebra_glm::{Mat4, Vec3, Vec4};
+use crate::{color::Color, culling::clip_planes, framebuffer::Framebuffer};
+
+// Rasterizador de líneas
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LineStyle {
+    // Bresenham: un píxel por paso, sin suavizado
+    Bresenham,
+    // Xiaolin Wu: reparte cada paso entre los dos píxeles vecinos según la distancia
+    Wu,
+}
+
+// Línea entre dos puntos de pantalla (x, y, profundidad) con prueba de profundidad
+// y sin escribirla; `alpha` es la opacidad de la línea sobre lo ya dibujado
+pub fn draw_line(framebuffer: &mut Framebuffer, a: Vec3, b: Vec3, color: Color, alpha: f32, style: LineStyle) {
+    // margen de un píxel para que Wu conserve el vecino parcial del borde
+    let (w, h) = (framebuffer.width as f32, framebuffer.height as f32);
+    let Some((a, b)) = clip_to_rect(a, b, -1.0, -1.0, w, h) else {
+        return;
+    };
+    match style {
+        LineStyle::Bresenham => bresenham(framebuffer, a, b, color, alpha),
+        LineStyle::Wu => wu(framebuffer, a, b, color, alpha),
+    }
+}
+
+// Liang–Barsky: recorta el segmento al rectángulo [x0, x1] x [y0, y1] e interpola la
+// profundidad. None si queda entero fuera; así no se recorren píxeles invisibles.
+fn clip_to_rect(a: Vec3, b: Vec3, x0: f32, y0: f32, x1: f32, y1: f32) -> Option<(Vec3, Vec3)> {
+    let d = b - a;
+    let (mut t0, mut t1) = (0.0f32, 1.0f32);
+    for (p, q) in [(-d.x, a.x - x0), (d.x, x1 - a.x), (-d.y, a.y - y0), (d.y, y1 - a.y)] {
+        if p == 0.0 {
+            // paralelo a este borde: dentro o fuera por completo
+            if q < 0.0 {
+                return None;
+            }
+            continue;
+        }
+        let t = q / p;
+        if p < 0.0 {
+            t0 = t0.max(t);
+        } else {
+            t1 = t1.min(t);
+        }
+        if t0 > t1 {
+            return None;
+        }
+    }
+    Some((a + d * t0, a + d * t1))
+}
+
+fn bresenham(framebuffer: &mut Framebuffer, a: Vec3, b: Vec3, color: Color, alpha: f32) {
+    let (mut x, mut y) = (a.x.round() as i32, a.y.round() as i32);
+    let (x1, y1) = (b.x.round() as i32, b.y.round() as i32);
+    let (dx, dy) = ((x1 - x).abs(), -(y1 - y).abs());
+    let (sx, sy) = (if x < x1 { 1 } else { -1 }, if y < y1 { 1 } else { -1 });
+    let steps = dx.max(-dy).max(1) as f32;
+    let mut err = dx + dy;
+    let mut i = 0.0;
+    loop {
+        let depth = a.z + (b.z - a.z) * (i / steps);
+        framebuffer.plot(x, y, depth, color, alpha);
+        if x == x1 && y == y1 {
+            break;
+        }
+        let e2 = 2 * err;
+        if e2 >= dy {
+            err += dy;
+            x += sx;
+        }
+        if e2 <= dx {
+            err += dx;
+            y += sy;
+        }
+        i += 1.0;
+    }
+}
+
+fn wu(framebuffer: &mut Framebuffer, a: Vec3, b: Vec3, color: Color, alpha: f32) {
+    // se recorre siempre el eje largo; `steep` intercambia x e y
+    let steep = (b.y - a.y).abs() > (b.x - a.x).abs();
+    let (mut a, mut b) = if steep { (Vec3::new(a.y, a.x, a.z), Vec3::new(b.y, b.x, b.z)) } else { (a, b) };
+    if a.x > b.x {
+        std::mem::swap(&mut a, &mut b);
+    }
+    let dx = b.x - a.x;
+    let gradient = if dx.abs() < 1e-6 { 1.0 } else { (b.y - a.y) / dx };
+    let mut plot = |x: i32, y: i32, depth: f32, coverage: f32| {
+        let (px, py) = if steep { (y, x) } else { (x, y) };
+        framebuffer.plot(px, py, depth, color, alpha * coverage);
+    };
+
+    // extremos: cobertura parcial según cuánto del píxel ocupa la línea
+    let x_start = a.x.round();
+    let x_end = b.x.round();
+    let y_start = a.y + gradient * (x_start - a.x);
+    let gap_start = 1.0 - (a.x + 0.5).fract();
+    let gap_end = (b.x + 0.5).fract();
+    plot(x_start as i32, y_start.floor() as i32, a.z, (1.0 - y_start.fract()) * gap_start);
+    plot(x_start as i32, y_start.floor() as i32 + 1, a.z, y_start.fract() * gap_start);
+    let y_last = b.y + gradient * (x_end - b.x);
+    plot(x_end as i32, y_last.floor() as i32, b.z, (1.0 - y_last.fract()) * gap_end);
+    plot(x_end as i32, y_last.floor() as i32 + 1, b.z, y_last.fract() * gap_end);
+
+    let span = (x_end - x_start).max(1.0);
+    let mut y = y_start + gradient;
+    for x in (x_start as i32 + 1)..(x_end as i32) {
+        let depth = a.z + (b.z - a.z) * ((x as f32 - x_start) / span);
+        plot(x, y.floor() as i32, depth, 1.0 - y.fract());
+        plot(x, y.floor() as i32 + 1, depth, y.fract());
+        y += gradient;
+    }
+}
+
+// Segmento en el mundo: se recorta contra el plano cercano en espacio de vista y
+// se proyecta a pantalla. None si queda entero detrás de la cámara.
+pub fn project_segment(a: Vec3, b: Vec3, view: &Mat4, projection: &Mat4, viewport: &Mat4) -> Option<(Vec3, Vec3)> {
+    let mut va = view * Vec4::new(a.x, a.y, a.z, 1.0);
+    let mut vb = view * Vec4::new(b.x, b.y, b.z, 1.0);
+    // delante de la cámara es z < -near
+    let (near, _) = clip_planes(projection);
+    match (va.z <= -near, vb.z <= -near) {
+        (false, false) => return None,
+        (true, false) => vb = va + (vb - va) * ((-near - va.z) / (vb.z - va.z)),
+        (false, true) => va = vb + (va - vb) * ((-near - vb.z) / (va.z - vb.z)),
+        (true, true) => {}
+    }
+    let to_screen = |v: Vec4| {
+        let clip = projection * v;
+        let ndc = Vec4::new(clip.x / clip.w, clip.y / clip.w, clip.z / clip.w, 1.0);
+        let s = viewport * ndc;
+        Vec3::new(s.x, s.y, s.z)
+    };
+    Some((to_screen(va), to_screen(vb)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clip_keeps_segments_inside() {
+        let (a, b) = (Vec3::new(1.0, 2.0, 0.1), Vec3::new(8.0, 5.0, 0.3));
+        assert_eq!(clip_to_rect(a, b, 0.0, 0.0, 10.0, 10.0), Some((a, b)));
+    }
+
+    #[test]
+    fn clip_cuts_to_the_rect_and_interpolates_depth() {
+        let a = Vec3::new(-10.0, 5.0, 0.0);
+        let b = Vec3::new(20.0, 5.0, 0.3);
+        let (ca, cb) = clip_to_rect(a, b, 0.0, 0.0, 10.0, 10.0).unwrap();
+        assert!((ca - Vec3::new(0.0, 5.0, 0.1)).norm() < 1e-5);
+        assert!((cb - Vec3::new(10.0, 5.0, 0.2)).norm() < 1e-5);
+    }
+
+    #[test]
+    fn clip_rejects_segments_outside() {
+        let r = |a: Vec3, b: Vec3| clip_to_rect(a, b, 0.0, 0.0, 10.0, 10.0);
+        assert_eq!(r(Vec3::new(-5.0, -1.0, 0.0), Vec3::new(-1.0, 20.0, 0.0)), None);
+        assert_eq!(r(Vec3::new(-5.0, 12.0, 0.0), Vec3::new(30.0, 12.0, 0.0)), None);
+        // cruza la esquina por fuera
+        assert_eq!(r(Vec3::new(-5.0, 3.0, 0.0), Vec3::new(3.0, -5.0, 0.0)), None);
+    }
+}
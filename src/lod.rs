@@ -0,0 +1,136 @@
+use nalgebra_glm::{Mat4, Vec3, Vec4};
+use crate::{color::Color, framebuffer::Framebuffer};
+use crate::vertex::Vertex;
+
+// Conjunto de niveles de detalle de una malla, de menor a mayor detalle.
+// thresholds[i] = radio proyectado mínimo (en px) para usar el nivel i.
+pub struct LodMesh {
+    pub levels: Vec<Vec<Vertex>>,
+    pub thresholds: Vec<f32>,
+}
+
+impl LodMesh {
+    pub fn new(levels: Vec<Vec<Vertex>>, thresholds: Vec<f32>) -> Self {
+        assert_eq!(levels.len(), thresholds.len(), "one threshold per LOD level");
+        Self { levels, thresholds }
+    }
+
+    // Malla sin niveles alternativos (p. ej. un OBJ cargado por argumento)
+    pub fn single(vertices: Vec<Vertex>) -> Self {
+        Self { levels: vec![vertices], thresholds: vec![0.0] }
+    }
+
+    pub fn len(&self) -> usize {
+        self.levels.len()
+    }
+}
+
+// Estado de selección por cuerpo; recuerda el nivel activo para aplicar histéresis.
+#[derive(Clone)]
+pub struct LodSelector {
+    current: usize,
+    hysteresis: f32,
+}
+
+impl LodSelector {
+    pub fn new(hysteresis: f32) -> Self {
+        Self { current: 0, hysteresis }
+    }
+
+    // Sube de nivel sólo si el radio supera el umbral por encima del margen,
+    // y baja sólo si queda por debajo del margen inferior; evita el "popping".
+    pub fn select(&mut self, lods: &LodMesh, screen_radius: f32) -> usize {
+        let last = lods.len() - 1;
+        self.current = self.current.min(last);
+        while self.current < last && screen_radius > lods.thresholds[self.current + 1] * (1.0 + self.hysteresis) {
+            self.current += 1;
+        }
+        while self.current > 0 && screen_radius < lods.thresholds[self.current] * (1.0 - self.hysteresis) {
+            self.current -= 1;
+        }
+        self.current
+    }
+}
+
+// Proyecta el centro de una esfera a pantalla. None si queda detrás de la cámara.
+pub fn project_point(p: Vec3, view: &Mat4, projection: &Mat4, viewport: &Mat4) -> Option<Vec3> {
+    let clip = projection * view * Vec4::new(p.x, p.y, p.z, 1.0);
+    if clip.w <= 0.0 {
+        return None;
+    }
+    let ndc = Vec4::new(clip.x / clip.w, clip.y / clip.w, clip.z / clip.w, 1.0);
+    let screen = viewport * ndc;
+    Some(Vec3::new(screen.x, screen.y, screen.z))
+}
+
+// Radio aproximado en píxeles de una esfera de radio `radius` en `center` (mundo)
+pub fn projected_radius(center: Vec3, radius: f32, view: &Mat4, projection: &Mat4, viewport_height: f32) -> f32 {
+    let view_pos = view * Vec4::new(center.x, center.y, center.z, 1.0);
+    let dist = (-view_pos.z).max(1e-3);
+    // projection[(1,1)] = 1 / tan(fovy/2)
+    radius * projection[(1, 1)] * viewport_height * 0.5 / dist
+}
+
+// Overlay de depuración: círculo del radio proyectado con color por nivel
+pub fn draw_lod_overlay(framebuffer: &mut Framebuffer, center: Vec3, radius: f32, level: usize) {
+    const LEVEL_COLORS: [u32; 5] = [0xFF3030, 0xFFA020, 0xFFFF30, 0x30FF60, 0x30C0FF];
+    let color = LEVEL_COLORS[level.min(LEVEL_COLORS.len() - 1)];
+    framebuffer.set_current_color(Color::from_hex(color));
+
+    let steps = ((radius * std::f32::consts::TAU) as usize).max(16);
+    for i in 0..steps {
+        let a = i as f32 * std::f32::consts::TAU / steps as f32;
+        let x = center.x + a.cos() * radius;
+        let y = center.y + a.sin() * radius;
+        framebuffer.set_pixel(x as i32, y as i32);
+    }
+
+    // marcas bajo el círculo: una por nivel (0 = una marca)
+    let base_y = (center.y + radius + 6.0) as i32;
+    for l in 0..=level {
+        let bx = center.x as i32 - (level as i32 * 6) / 2 + l as i32 * 6;
+        for dy in 0..4 {
+            for dx in 0..4 {
+                framebuffer.set_pixel(bx + dx - 2, base_y + dy);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // tres niveles vacíos con umbrales 0, 50 y 100 px
+    fn three_levels() -> LodMesh {
+        LodMesh::new(vec![Vec::new(), Vec::new(), Vec::new()], vec![0.0, 50.0, 100.0])
+    }
+
+    #[test]
+    fn select_climbs_past_every_threshold_at_once() {
+        let lods = three_levels();
+        let mut selector = LodSelector::new(0.1);
+        assert_eq!(selector.select(&lods, 10.0), 0);
+        assert_eq!(selector.select(&lods, 200.0), 2);
+        assert_eq!(selector.select(&lods, 1.0), 0);
+    }
+
+    #[test]
+    fn select_holds_the_level_inside_the_hysteresis_band() {
+        let lods = three_levels();
+        let mut selector = LodSelector::new(0.1);
+        // sube sólo por encima de 50 * 1.1
+        assert_eq!(selector.select(&lods, 54.0), 0);
+        assert_eq!(selector.select(&lods, 56.0), 1);
+        // baja sólo por debajo de 50 * 0.9
+        assert_eq!(selector.select(&lods, 46.0), 1);
+        assert_eq!(selector.select(&lods, 44.0), 0);
+    }
+
+    #[test]
+    fn select_clamps_to_meshes_with_fewer_levels() {
+        let mut selector = LodSelector::new(0.1);
+        assert_eq!(selector.select(&three_levels(), 200.0), 2);
+        assert_eq!(selector.select(&LodMesh::single(Vec::new()), 200.0), 0);
+    }
+}
@@ -6,16 +6,58 @@ mod shaders;
 mod triangle;
 mod vertex;
 mod ring;
+mod texture;
+mod icosphere;
+mod lod;
+mod culling;
+mod light;
+mod shadow;
+mod shadow_map;
+mod atmosphere;
+mod clouds;
+mod craters;
+mod glow;
+mod tonemap;
+mod bloom;
+mod antialias;
+mod screenshot;
+mod recorder;
+mod rng;
+mod sky;
+mod line;
+mod debug_view;
+mod text;
+mod hud;
+mod orbit;
 
-use color::Color;
+use antialias::{fxaa, AntiAliasing};
+use atmosphere::Atmosphere;
+use bloom::Bloom;
+use clouds::CloudLayer;
+use color::{Color, ColorPipeline};
+use craters::CraterField;
 use fastnoise_lite::{FastNoiseLite, FractalType, NoiseType};
-use framebuffer::Framebuffer;
-use image::{ImageBuffer, Rgb};
+use framebuffer::{BlendMode, Framebuffer};
+use glow::StarGlow;
 use minifb::{Key, Window, WindowOptions};
-use nalgebra_glm::{look_at, perspective, Mat4, Vec2, Vec3, Vec4};
+use nalgebra_glm::{look_at, mat4_to_mat3, perspective, Mat4, Vec3};
 use obj::Obj;
-use shaders::{fragment_star, fragment_gas, fragment_rocky, fragment_moon, fragment_ring, vertex_shader};
-use ring::build_ring;
+use shaders::{fragment_star, fragment_gas, fragment_rocky, fragment_moon, fragment_ring, fragment_atmosphere, fragment_clouds, vertex_shader};
+use ring::{Ring, RingProfile};
+use icosphere::build_icosphere;
+use hud::{draw_hud, FrameTimer, HudInfo};
+use debug_view::{DebugPass, DebugView};
+use culling::{sphere_screen_bounds, Frustum, HiZ};
+use light::Light;
+use shadow::Occluder;
+use recorder::{RecordConfig, Recorder};
+use screenshot::ScreenshotConfig;
+use shadow_map::ShadowMap;
+use sky::{SkyMap, Starfield};
+use tonemap::{ToneMapper, ToneMapping};
+use line::LineStyle;
+use orbit::Orbit;
+use lod::{draw_lod_overlay, project_point, projected_radius, LodMesh, LodSelector};
 use triangle::triangle;
 use vertex::Vertex;
 
@@ -25,15 +67,29 @@ pub struct Uniforms<'a> {
     pub projection_matrix: Mat4,
     pub viewport_matrix: Mat4,
     pub time: f32,
+    pub pipeline: ColorPipeline,
     pub noises: Vec<&'a FastNoiseLite>,
+    pub lights: &'a [Light],
+    pub camera_position: Vec3,
+    pub occluders: &'a [Occluder<'a>],
+    pub shadow_map: Option<&'a ShadowMap>,
+    pub atmosphere: Option<Atmosphere>,
+    pub clouds: Option<CloudLayer<'a>>,
+    pub craters: Option<&'a CraterField>,
+    pub ring: Option<&'a RingProfile>,
 }
 
 fn create_view_matrix(eye: Vec3, center: Vec3, up: Vec3) -> Mat4 {
     look_at(&eye, &center, &up)
 }
 
+// Campo de visión vertical (rad): el encuadre vertical de siempre en la ventana.
+// El aspecto sale del tamaño del framebuffer, así que nada queda estirado.
+const FOV_Y: f32 = 1.0;
+
 fn create_perspective_matrix(w: f32, h: f32) -> Mat4 {
-    perspective(45f32.to_radians(), w / h, 0.1, 1000.0)
+    // nalgebra_glm::perspective recibe (aspect, fovy, near, far)
+    perspective(w / h, FOV_Y, 0.1, 1000.0)
 }
 
 fn create_viewport_matrix(width: f32, height: f32) -> Mat4 {
@@ -126,11 +182,109 @@ fn create_noise_fbmn(seed: i32, freq: f32, octaves: i32) -> FastNoiseLite {
     n
 }
 
+// Radio de sphere.obj en espacio de objeto (vértice más lejano del origen: la malla
+// está algo descentrada); las icosferas se generan con el mismo
+const SPHERE_RADIUS: f32 = 0.524;
+
+// Elige el nivel de detalle de un cuerpo según su radio proyectado en pantalla
+fn select_lod(
+    selector: &mut LodSelector,
+    lods: &LodMesh,
+    center: Vec3,
+    scale: f32,
+    view: &Mat4,
+    projection: &Mat4,
+    viewport_height: f32,
+) -> (usize, f32) {
+    let r = projected_radius(center, scale * SPHERE_RADIUS, view, projection, viewport_height);
+    (selector.select(lods, r), r)
+}
+
+// Cuerpo listo para dibujar: geometría, shader y esfera envolvente en mundo
+struct Body<'a> {
+    uniforms: Uniforms<'a>,
+    vertices: &'a [Vertex],
+    shader: fn(&fragment::Fragment, &Uniforms) -> Color,
+    center: Vec3,
+    radius: f32,
+    blend: BlendMode,
+}
+
+// Malla que proyecta sombra en el mapa; `coverage` recorta sus zonas transparentes
+struct Caster<'a> {
+    vertices: &'a [Vertex],
+    model: Mat4,
+    center: Vec3,
+    radius: f32,
+    coverage: Option<&'a RingProfile>,
+}
+
+impl<'a> Caster<'a> {
+    fn opaque(vertices: &'a [Vertex], model: Mat4, center: Vec3, radius: f32) -> Self {
+        Self { vertices, model, center, radius, coverage: None }
+    }
+}
+
+// Dibuja primero lo opaco de cerca a lejos y después lo mezclado de lejos a cerca.
+// Con culling activo descarta los que quedan fuera del frustum y los tapados
+// por cuerpos opacos ya dibujados (Hi-Z).
+fn render_bodies(framebuffer: &mut Framebuffer, bodies: &mut [Body], eye: Vec3, culling: bool, debug: &mut DebugPass) {
+    bodies.sort_by(|a, b| {
+        let da = (a.center - eye).norm() - a.radius;
+        let db = (b.center - eye).norm() - b.radius;
+        let opaque_a = a.blend == BlendMode::Replace;
+        let opaque_b = b.blend == BlendMode::Replace;
+        match (opaque_a, opaque_b) {
+            (true, false) => std::cmp::Ordering::Less,
+            (false, true) => std::cmp::Ordering::Greater,
+            (true, true) => da.total_cmp(&db),
+            (false, false) => db.total_cmp(&da),
+        }
+    });
+
+    let mut hiz: Option<HiZ> = None;
+    for body in bodies.iter() {
+        if culling {
+            let u = &body.uniforms;
+            let frustum = Frustum::from_matrix(&(u.projection_matrix * u.view_matrix));
+            if !frustum.intersects_sphere(body.center, body.radius) {
+                continue;
+            }
+            if let Some(bounds) = sphere_screen_bounds(body.center, body.radius, &u.view_matrix, &u.projection_matrix, &u.viewport_matrix) {
+                let hiz = hiz.get_or_insert_with(|| HiZ::build(framebuffer));
+                if hiz.is_occluded(&bounds) {
+                    continue;
+                }
+            }
+        }
+        framebuffer.set_blend_mode(if debug.view.forces_opaque() { BlendMode::Replace } else { body.blend });
+        render(framebuffer, &body.uniforms, body.vertices, body.shader, debug);
+        if body.blend == BlendMode::Replace {
+            // la pirámide queda desactualizada tras dibujar algo opaco
+            hiz = None;
+        }
+    }
+    framebuffer.set_blend_mode(BlendMode::Replace);
+}
+
+// Capa de atmósfera: esfera algo mayor que el planeta, sumada tras lo opaco
+fn atmosphere_body<'a>(atmosphere: Atmosphere, vertices: &'a [Vertex], uniforms: Uniforms<'a>) -> Body<'a> {
+    Body {
+        uniforms,
+        vertices,
+        shader: fragment_atmosphere,
+        center: atmosphere.center,
+        radius: atmosphere.shell_radius(),
+        blend: BlendMode::Additive,
+    }
+}
+
 fn render(
     framebuffer: &mut Framebuffer,
     uniforms: &Uniforms,
     vertex_array: &[Vertex],
     shader_fn: fn(&fragment::Fragment, &Uniforms) -> Color,
+    debug: &mut DebugPass,
 ) {
     // Vertex stage
     let mut transformed = Vec::with_capacity(vertex_array.len());
@@ -146,223 +300,847 @@ fn render(
         }
     }
 
-    // Raster
-    let mut fragments = Vec::new();
-    for tri in &tris {
-        fragments.extend(triangle(&tri[0], &tri[1], &tri[2]));
+    // Raster + fragment stage, triángulo a triángulo (los modos de depuración
+    // necesitan saber de qué triángulo sale cada fragmento)
+    let samples = framebuffer.sample_pattern();
+    for (index, tri) in tris.iter().enumerate() {
+        debug.record_triangle([tri[0].transformed_position, tri[1].transformed_position, tri[2].transformed_position]);
+        for frag in triangle(&tri[0], &tri[1], &tri[2], samples, framebuffer.width, framebuffer.height) {
+            let color = debug.shade(&frag, index).unwrap_or_else(|| shader_fn(&frag, uniforms));
+            framebuffer.set_current_color(color);
+            framebuffer.point_samples(frag.position.x as i32, frag.position.y as i32, frag.coverage, &frag.sample_depths);
+        }
     }
+}
 
-    // Fragment stage
-    for frag in fragments {
-        let x = frag.position.x as usize;
-        let y = frag.position.y as usize;
-        if x < framebuffer.width && y < framebuffer.height {
-            let color = shader_fn(&frag, uniforms).to_hex();
-            framebuffer.set_current_color(color);
-            framebuffer.point(x as i32, y as i32, frag.depth);
+// Recursos de la escena que no cambian entre cuadros: mallas, ruidos, anillos, cráteres
+struct Scene {
+    eye: Vec3,
+    center: Vec3,
+    up: Vec3,
+    sphere_lods: LodMesh,
+    star_base: FastNoiseLite,
+    star_spots: FastNoiseLite,
+    star_gran: FastNoiseLite,
+    rocky_base: FastNoiseLite,
+    rocky_detail: FastNoiseLite,
+    rocky_biome: FastNoiseLite,
+    rocky_clouds: FastNoiseLite,
+    rocky_cities: FastNoiseLite,
+    gas_bands: FastNoiseLite,
+    gas_detail: FastNoiseLite,
+    gas_storms: FastNoiseLite,
+    moon_craters: CraterField,
+    gas_ring: Ring,
+    starfield: Starfield,
+    // fondo con imágenes (SKYBOX) y su brillo
+    skybox: Option<SkyMap>,
+    skybox_intensity: f32,
+}
+
+impl Scene {
+    fn new(eye: Vec3, sphere_lods: LodMesh) -> Self {
+        Self {
+            eye,
+            center: Vec3::new(0.0, 0.0, 0.0),
+            up: Vec3::new(0.0, 1.0, 0.0),
+            sphere_lods,
+            // Star: base, spots, granulation
+            star_base: create_noise_fbmn(42, 0.005, 6),
+            star_spots: create_noise_fbmn(43, 0.02, 5),
+            star_gran: create_noise_fbmn(44, 0.08, 4),
+            // Rocky: base, detail, biome, cities; clouds van en su propia capa
+            rocky_base: create_noise_fbmn(7, 1.0, 5),
+            rocky_detail: create_noise_fbmn(8, 3.0, 3),
+            rocky_biome: create_noise_fbmn(9, 0.6, 3),
+            rocky_clouds: create_noise_fbmn(10, 0.9, 5),
+            rocky_cities: create_noise_fbmn(11, 4.0, 3),
+            // Gas: bands, detail, storms
+            gas_bands: create_noise_fbmn(99, 2.0, 2),
+            gas_detail: create_noise_fbmn(100, 1.2, 3),
+            gas_storms: create_noise_fbmn(101, 0.9, 4),
+            // Moon: campo de cráteres (densidad y rango de tamaños)
+            moon_craters: CraterField::generate(2024, 400, 0.02, 0.3, 1.8),
+            // Anillos por cuerpo: radios (unidades del modelo) y perfil radial de color/opacidad
+            gas_ring: Ring::new(1.2, 2.0, RingProfile::load_or_procedural("assets/textures/saturn_ring.png")),
+            // Cielo: estrellas con semilla y banda galáctica
+            starfield: Starfield::generate(1977, 6000),
+            skybox: load_skybox(),
+            skybox_intensity: std::env::var("SKYBOX_INTENSITY").ok().and_then(|s| s.trim().parse().ok()).unwrap_or(1.0),
         }
     }
 }
 
-fn main() -> Result<(), Box<dyn std::error::Error>> {
-    // Window
-    let w = 800usize;
-    let h = 800usize;
-    let mut window = Window::new("Lab5 - Planetas", w, h, WindowOptions::default())?;
-    let mut fb = Framebuffer::new(w, h);
+// Opciones que se cambian con el teclado
+#[derive(Clone, Copy)]
+struct Settings {
+    mode: u32, // 0 = todos, 1=estrella, 2=rocoso, 3=gaseoso
+    animate_orbits: bool,
+    orbit_trails: bool,
+    line_style: LineStyle,
+    show_lod: bool,
+    culling: bool,
+    shadows: bool,
+    shadow_mapping: bool,
+    atmospheres: bool,
+    clouds: bool,
+    star_glow: bool,
+    lens_flare: bool,
+    bloom: bool,
+    stars: bool,
+    galactic_band: bool,
+    skybox: bool,
+    antialiasing: AntiAliasing,
+    tone_mapping: ToneMapping,
+    color_pipeline: ColorPipeline,
+    debug_view: DebugView,
+}
 
-    // Camera
-    let eye = Vec3::new(0.0, 6.0, 22.0);
-    let center = Vec3::new(0.0, 0.0, 0.0);
-    let up = Vec3::new(0.0, 1.0, 0.0);
+// Lo que se ve al arrancar
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            mode: 0,
+            animate_orbits: false,
+            orbit_trails: true,
+            line_style: LineStyle::Wu,
+            show_lod: false,
+            culling: true,
+            shadows: true,
+            shadow_mapping: true,
+            atmospheres: true,
+            clouds: true,
+            star_glow: true,
+            lens_flare: false,
+            bloom: true,
+            stars: true,
+            galactic_band: true,
+            skybox: true,
+            antialiasing: AntiAliasing::Msaa4x,
+            // recorte: el aspecto de siempre; Reinhard y ACES se eligen con T
+            tone_mapping: ToneMapping { operator: ToneMapper::Clamp, exposure: 1.0 },
+            color_pipeline: ColorPipeline::Linear,
+            debug_view: DebugView::Off,
+        }
+    }
+}
 
-    // Matrices
-    let projection = create_perspective_matrix(w as f32, h as f32);
-    let viewport = create_viewport_matrix(w as f32, h as f32);
+// Tiempo de simulación: `time` en ms y el giro acumulado de los cuerpos
+struct Clock {
+    time: f32,
+    rotation: f32,
+}
 
-    // Load sphere model
-    let args: Vec<String> = std::env::args().collect();
-        let sphere_path = if args.len() > 1 {
-            args[1].clone()
-        } else {
-            "assets/models/sphere.obj".to_string()
-        };
-    let obj = Obj::load(&sphere_path)?;
-    let sphere_vertices = obj.get_vertex_array();
-
-    // Noises per shader
-    // Star: base, spots, granulation
-    let star_base = create_noise_fbmn(42, 0.005, 6);
-    let star_spots = create_noise_fbmn(43, 0.02, 5);
-    let star_gran  = create_noise_fbmn(44, 0.08, 4);
-
-    // Rocky: base, detail, biome, clouds
-    let rocky_base = create_noise_fbmn(7, 1.0, 5);
-    let rocky_detail = create_noise_fbmn(8, 3.0, 3);
-    let rocky_biome = create_noise_fbmn(9, 0.6, 3);
-    let rocky_clouds = create_noise_fbmn(10, 0.9, 5);
-
-    // Gas: bands, detail, storms
-    let gas_bands = create_noise_fbmn(99, 2.0, 2);
-    let gas_detail = create_noise_fbmn(100, 1.2, 3);
-    let gas_storms = create_noise_fbmn(101, 0.9, 4);
-
-    let mut time = 0.0f32;
-    let mut mode = 0; // 0 = todos, 1=estrella, 2=rocoso, 3=gaseoso
-    let mut rotation = 0.0f32;
-    let mut animate_orbits = false;
-
-    // Precompute ring geometry (unit annulus in XY)
-    let ring_vertices = build_ring(1.2, 2.0, 64);
+impl Clock {
+    // Avanza `dt` ms; el giro va a 0.01 rad cada 16 ms
+    fn advance(&mut self, dt: f32) {
+        self.time += dt;
+        self.rotation += 0.01 * dt / 16.0;
+    }
+}
 
-    while window.is_open() && !window.is_key_down(Key::Escape) {
-        time += 16.0; // ms aprox
-        rotation += 0.01;
-        fb.clear(0x000000);
-
-        let view = create_view_matrix(eye, center, up);
-
-        // orbital positions
-        let tsec = time * 0.001;
-        let mut star_pos = Vec3::new(-8.0, 0.0, 0.0);
-        let mut rocky_pos = if animate_orbits {
-            let a = tsec * 0.8;
-            star_pos + Vec3::new(a.cos() * 6.0, 0.0, a.sin() * 6.0)
-        } else {
-            Vec3::new(0.0, 0.0, 0.0)
-        };
-        let mut gas_pos = if animate_orbits {
-            let a = tsec * 0.4;
-            star_pos + Vec3::new(a.cos() * 12.0, 0.0, a.sin() * 12.0)
-        } else {
-            Vec3::new(8.0, 0.0, 0.0)
-        };
-
-        // default scales
-        let mut star_scale = 2.4f32;
-        let mut rocky_scale = 2.0f32;
-        let mut gas_scale = 2.6f32;
-        let mut ring_scale = gas_scale;
-        let mut moon_scale = 0.6f32;
-
-        // focus zoom-in when selecting a single body
-        match mode {
-            1 => { // star focus
-                star_pos = Vec3::new(0.0, 0.0, 0.0);
-                star_scale = 3.6;
-            }
-            2 => { // rocky focus
-                rocky_pos = Vec3::new(0.0, 0.0, 0.0);
-                rocky_scale = 3.0;
-                moon_scale = 0.8;
-            }
-            3 => { // gas focus
-                gas_pos = Vec3::new(0.0, 0.0, 0.0);
-                gas_scale = 3.6;
-                ring_scale = gas_scale;
-            }
-            _ => {}
+// Nivel de detalle activo de cada cuerpo (con histéresis entre cuadros)
+#[derive(Clone)]
+struct LodState {
+    star: LodSelector,
+    rocky: LodSelector,
+    moon: LodSelector,
+    gas: LodSelector,
+}
+
+// Lo que una vista arrastra entre cuadros: LODs y buffers de los pases
+struct ViewState {
+    lods: LodState,
+    shadow_map: ShadowMap,
+    bloom: Bloom,
+    debug: DebugPass,
+}
+
+impl ViewState {
+    fn new() -> Self {
+        Self {
+            lods: LodState {
+                star: LodSelector::new(0.15),
+                rocky: LodSelector::new(0.15),
+                moon: LodSelector::new(0.15),
+                gas: LodSelector::new(0.15),
+            },
+            shadow_map: ShadowMap::new(512),
+            bloom: Bloom::new(0.6, 1.0),
+            debug: DebugPass::new(),
+        }
+    }
+}
+
+// Dibuja un cuadro completo en `fb` (geometría, post-proceso y resolve a `buffer`).
+// La proyección sale del tamaño del framebuffer, así que sirve igual para la ventana
+// que para capturas a otra resolución.
+fn render_frame(fb: &mut Framebuffer, scene: &Scene, settings: &Settings, clock: &Clock, state: &mut ViewState) {
+    let Settings {
+        mode,
+        animate_orbits,
+        orbit_trails,
+        line_style,
+        show_lod,
+        culling,
+        shadows,
+        shadow_mapping,
+        atmospheres,
+        clouds,
+        star_glow,
+        lens_flare,
+        bloom,
+        stars,
+        galactic_band,
+        skybox,
+        antialiasing,
+        tone_mapping,
+        color_pipeline,
+        debug_view,
+    } = *settings;
+    let Clock { time, rotation } = *clock;
+    let eye = scene.eye;
+
+    fb.clear(Color::new(0, 0, 0));
+
+    let projection = create_perspective_matrix(fb.width as f32, fb.height as f32);
+    let viewport = create_viewport_matrix(fb.width as f32, fb.height as f32);
+    let view = create_view_matrix(eye, scene.center, scene.up);
+    let mut lod_overlays = Vec::new();
+    let mut bodies: Vec<Body> = Vec::new();
+
+    // Fondo fijo en el mundo, antes que los cuerpos: la imagen de cielo si hay una
+    // cargada y activa, si no las estrellas procedurales
+    // los modos de depuración de color falso se ven sin fondo, estelas ni post-proceso
+    let post = !debug_view.replaces_image();
+    state.debug.begin(debug_view, color_pipeline, &projection, fb);
+    match &scene.skybox {
+        _ if !post => {}
+        Some(sky) if skybox => sky.draw(fb, &view, &projection, scene.skybox_intensity, color_pipeline),
+        _ if stars => scene.starfield.draw(fb, &view, &projection, &viewport, galactic_band),
+        _ => {}
+    }
+
+    // orbital positions
+    let tsec = time * 0.001;
+    let mut star_pos = Vec3::new(-8.0, 0.0, 0.0);
+    let rocky_orbit = Orbit::circular(star_pos, 6.0, 0.8);
+    let gas_orbit = Orbit::circular(star_pos, 12.0, 0.4);
+    let mut rocky_pos = if animate_orbits {
+        rocky_orbit.position(tsec)
+    } else {
+        Vec3::new(0.0, 0.0, 0.0)
+    };
+    let mut gas_pos = if animate_orbits {
+        gas_orbit.position(tsec)
+    } else {
+        Vec3::new(8.0, 0.0, 0.0)
+    };
+
+    // default scales
+    let mut star_scale = 2.4f32;
+    let mut rocky_scale = 2.0f32;
+    let mut gas_scale = 2.6f32;
+    let mut ring_scale = gas_scale;
+    let mut moon_scale = 0.6f32;
+
+    // focus zoom-in when selecting a single body
+    match mode {
+        1 => { // star focus
+            star_pos = Vec3::new(0.0, 0.0, 0.0);
+            star_scale = 3.6;
         }
+        2 => { // rocky focus
+            rocky_pos = Vec3::new(0.0, 0.0, 0.0);
+            rocky_scale = 3.0;
+            moon_scale = 0.8;
+        }
+        3 => { // gas focus
+            gas_pos = Vec3::new(0.0, 0.0, 0.0);
+            gas_scale = 3.6;
+            ring_scale = gas_scale;
+        }
+        _ => {}
+    }
 
-        // Estrella
-        if mode == 0 || mode == 1 {
-            let uniforms_star = Uniforms {
-                model_matrix: create_model_matrix(star_pos, star_scale, rotation),
+    // Moon orbiting rocky planet (always visible)
+    let moon_center = if animate_orbits { rocky_pos } else { Vec3::new(0.0, 0.0, 0.0) };
+    let orbit_r = if mode == 2 { 2.2 } else { 3.2 };
+    let moon_orbit = Orbit { center: moon_center, radius: orbit_r, angular_speed: 2.5, bob: 0.5, bob_rate: 0.7 };
+    let moon_pos = moon_orbit.position(tsec);
+
+    // Estelas de lo que se mueve de verdad en su órbita: los planetas sólo con las
+    // órbitas animadas y en la vista general (en foco el cuerpo queda en el origen)
+    let mut trails = Vec::new();
+    if orbit_trails && post {
+        if animate_orbits && mode == 0 {
+            trails.push((rocky_orbit, color_pipeline.srgb(0.35, 0.6, 1.0)));
+            trails.push((gas_orbit, color_pipeline.srgb(0.95, 0.75, 0.45)));
+        }
+        if mode == 0 || mode == 2 {
+            trails.push((moon_orbit, color_pipeline.srgb(0.75, 0.75, 0.8)));
+        }
+    }
+
+    // Rings around gas giant (tilted ring in XZ plane) - always visible
+    let ring_rot_x = -std::f32::consts::FRAC_PI_2 * 0.9; // slight tilt
+    let ring_model = create_model_matrix_euler(gas_pos, ring_scale, ring_rot_x, rotation * 0.2, 0.0);
+
+    let star_model = create_model_matrix(star_pos, star_scale, rotation);
+    let rocky_model = create_model_matrix(rocky_pos, rocky_scale, -rotation * 0.3);
+    let moon_model = create_model_matrix(moon_pos, moon_scale, rotation * 0.5);
+    let gas_model = create_model_matrix(gas_pos, gas_scale, rotation * 0.8);
+
+    // La estrella es la fuente de luz aunque no se dibuje en el modo actual
+    let lights = [Light::point(star_pos, star_scale * SPHERE_RADIUS, color_pipeline.srgb(1.0, 0.96, 0.88), 1.6, 0.002)];
+
+    // Capa de nubes del rocoso, con giro propio
+    let cloud_layer = clouds.then_some(CloudLayer {
+        noise: &scene.rocky_clouds,
+        center: rocky_pos,
+        radius: rocky_scale * SPHERE_RADIUS * 1.03,
+        rotation: -rotation * 0.42,
+        coverage: 0.45,
+        opacity: 0.9,
+    });
+
+    // Nivel de detalle de cada esfera; el mapa de sombras usa la misma malla que se dibuja
+    let (star_level, star_r) = select_lod(&mut state.lods.star, &scene.sphere_lods, star_pos, star_scale, &view, &projection, fb.height as f32);
+    let (rocky_level, rocky_r) = select_lod(&mut state.lods.rocky, &scene.sphere_lods, rocky_pos, rocky_scale, &view, &projection, fb.height as f32);
+    let (moon_level, moon_r) = select_lod(&mut state.lods.moon, &scene.sphere_lods, moon_pos, moon_scale, &view, &projection, fb.height as f32);
+    let (gas_level, gas_r) = select_lod(&mut state.lods.gas, &scene.sphere_lods, gas_pos, gas_scale, &view, &projection, fb.height as f32);
+
+    // Cuerpos visibles que proyectan sombra sobre los demás: como esferas/anillos
+    // analíticos y como mallas (vértices, model matrix, centro, radio) para el mapa
+    let mut occluders = Vec::new();
+    let mut casters: Vec<Caster> = Vec::new();
+    if mode == 0 || mode == 2 {
+        occluders.push(Occluder::Sphere { center: rocky_pos, radius: rocky_scale * SPHERE_RADIUS });
+        occluders.push(Occluder::Sphere { center: moon_pos, radius: moon_scale * SPHERE_RADIUS });
+        casters.push(Caster::opaque(&scene.sphere_lods.levels[rocky_level], rocky_model, rocky_pos, rocky_scale * SPHERE_RADIUS));
+        casters.push(Caster::opaque(&scene.sphere_lods.levels[moon_level], moon_model, moon_pos, moon_scale * SPHERE_RADIUS));
+    }
+    if mode == 0 || mode == 3 {
+        occluders.push(Occluder::Sphere { center: gas_pos, radius: gas_scale * SPHERE_RADIUS });
+        let ring_normal = (mat4_to_mat3(&ring_model) * Vec3::new(0.0, 0.0, 1.0)).normalize();
+        occluders.push(Occluder::Ring {
+            center: gas_pos,
+            normal: ring_normal,
+            inner: ring_scale * scene.gas_ring.inner,
+            outer: ring_scale * scene.gas_ring.outer,
+            opacity: 1.0,
+            profile: &scene.gas_ring.profile,
+        });
+        casters.push(Caster::opaque(&scene.sphere_lods.levels[gas_level], gas_model, gas_pos, gas_scale * SPHERE_RADIUS));
+        casters.push(Caster {
+            coverage: Some(&scene.gas_ring.profile),
+            ..Caster::opaque(&scene.gas_ring.vertices, ring_model, gas_pos, ring_scale * scene.gas_ring.outer)
+        });
+    }
+    if !shadows {
+        occluders.clear();
+    }
+
+    // Mapa de sombras desde la estrella, una vista por caster
+    let shadow_map_ref = if shadow_mapping && !casters.is_empty() {
+        state.shadow_map.begin(star_pos);
+        for caster in &casters {
+            state.shadow_map.render_caster(caster.center, caster.radius, caster.vertices, &caster.model, caster.coverage);
+        }
+        Some(&state.shadow_map)
+    } else {
+        None
+    };
+
+    // Estrella
+    if mode == 0 || mode == 1 {
+        lod_overlays.push((star_pos, star_r, star_level));
+        bodies.push(Body {
+            uniforms: Uniforms {
+                model_matrix: star_model,
                 view_matrix: view,
                 projection_matrix: projection,
                 viewport_matrix: viewport,
                 time,
-                noises: vec![&star_base, &star_spots, &star_gran],
-            };
-            render(&mut fb, &uniforms_star, &sphere_vertices, fragment_star);
-        }
+                pipeline: color_pipeline,
+                noises: vec![&scene.star_base, &scene.star_spots, &scene.star_gran],
+                lights: &lights,
+                camera_position: eye,
+                occluders: &occluders,
+                shadow_map: shadow_map_ref,
+                atmosphere: None,
+                clouds: None,
+                craters: None,
+                ring: None,
+            },
+            vertices: &scene.sphere_lods.levels[star_level],
+            shader: fragment_star,
+            center: star_pos,
+            radius: star_scale * SPHERE_RADIUS,
+            blend: BlendMode::Replace,
+        });
+    }
 
-        // Rocoso
-        if mode == 0 || mode == 2 {
-            let uniforms_rocky = Uniforms {
-                model_matrix: create_model_matrix(rocky_pos, rocky_scale, -rotation * 0.3),
+    // Rocoso
+    if mode == 0 || mode == 2 {
+        lod_overlays.push((rocky_pos, rocky_r, rocky_level));
+        bodies.push(Body {
+            uniforms: Uniforms {
+                model_matrix: rocky_model,
                 view_matrix: view,
                 projection_matrix: projection,
                 viewport_matrix: viewport,
                 time,
-                noises: vec![&rocky_base, &rocky_detail, &rocky_biome, &rocky_clouds],
-            };
-            render(&mut fb, &uniforms_rocky, &sphere_vertices, fragment_rocky);
-
-            // Moon orbiting rocky planet (always visible)
-            let a = tsec * 2.5;
-            let moon_center = if animate_orbits { rocky_pos } else { Vec3::new(0.0, 0.0, 0.0) };
-            let orbit_r = if mode == 2 { 2.2 } else { 3.2 };
-            let moon_pos = moon_center + Vec3::new(a.cos() * orbit_r, 0.5 * (a * 0.7).sin(), a.sin() * orbit_r);
-            let uniforms_moon = Uniforms {
-                model_matrix: create_model_matrix(moon_pos, moon_scale, rotation * 0.5),
+                pipeline: color_pipeline,
+                noises: vec![&scene.rocky_base, &scene.rocky_detail, &scene.rocky_biome, &scene.rocky_cities],
+                lights: &lights,
+                camera_position: eye,
+                occluders: &occluders,
+                shadow_map: shadow_map_ref,
+                atmosphere: None,
+                clouds: cloud_layer,
+                craters: None,
+                ring: None,
+            },
+            vertices: &scene.sphere_lods.levels[rocky_level],
+            shader: fragment_rocky,
+            center: rocky_pos,
+            radius: rocky_scale * SPHERE_RADIUS,
+            blend: BlendMode::Replace,
+        });
+
+        lod_overlays.push((moon_pos, moon_r, moon_level));
+        bodies.push(Body {
+            uniforms: Uniforms {
+                model_matrix: moon_model,
                 view_matrix: view,
                 projection_matrix: projection,
                 viewport_matrix: viewport,
                 time,
-                noises: vec![&rocky_detail],
-            };
-            render(&mut fb, &uniforms_moon, &sphere_vertices, fragment_moon);
+                pipeline: color_pipeline,
+                noises: vec![&scene.rocky_detail],
+                lights: &lights,
+                camera_position: eye,
+                occluders: &occluders,
+                shadow_map: shadow_map_ref,
+                atmosphere: None,
+                clouds: None,
+                craters: Some(&scene.moon_craters),
+                ring: None,
+            },
+            vertices: &scene.sphere_lods.levels[moon_level],
+            shader: fragment_moon,
+            center: moon_pos,
+            radius: moon_scale * SPHERE_RADIUS,
+            blend: BlendMode::Replace,
+        });
+
+        if let Some(layer) = cloud_layer {
+            bodies.push(Body {
+                uniforms: Uniforms {
+                    model_matrix: create_model_matrix(rocky_pos, rocky_scale * 1.03, layer.rotation),
+                    view_matrix: view,
+                    projection_matrix: projection,
+                    viewport_matrix: viewport,
+                    time,
+                    pipeline: color_pipeline,
+                    noises: vec![],
+                    lights: &lights,
+                    camera_position: eye,
+                    occluders: &occluders,
+                    shadow_map: shadow_map_ref,
+                    atmosphere: None,
+                    clouds: Some(layer),
+                    craters: None,
+                    ring: None,
+                },
+                vertices: &scene.sphere_lods.levels[rocky_level],
+                shader: fragment_clouds,
+                center: rocky_pos,
+                radius: layer.radius,
+                blend: BlendMode::Alpha,
+            });
         }
 
-        // Gaseoso
-        if mode == 0 || mode == 3 {
-            let uniforms_gas = Uniforms {
-                model_matrix: create_model_matrix(gas_pos, gas_scale, rotation * 0.8),
+        if atmospheres {
+            let atmosphere = Atmosphere::earth_like(rocky_pos, rocky_scale * SPHERE_RADIUS);
+            bodies.push(atmosphere_body(atmosphere, &scene.sphere_lods.levels[rocky_level], Uniforms {
+                model_matrix: create_model_matrix(rocky_pos, rocky_scale * atmosphere.shell_scale, 0.0),
                 view_matrix: view,
                 projection_matrix: projection,
                 viewport_matrix: viewport,
                 time,
-                noises: vec![&gas_bands, &gas_detail, &gas_storms],
-            };
-            render(&mut fb, &uniforms_gas, &sphere_vertices, fragment_gas);
+                pipeline: color_pipeline,
+                noises: vec![],
+                lights: &lights,
+                camera_position: eye,
+                occluders: &occluders,
+                shadow_map: shadow_map_ref,
+                atmosphere: Some(atmosphere),
+                clouds: None,
+                craters: None,
+                ring: None,
+            }));
+        }
+    }
+
+    // Gaseoso
+    if mode == 0 || mode == 3 {
+        lod_overlays.push((gas_pos, gas_r, gas_level));
+        bodies.push(Body {
+            uniforms: Uniforms {
+                model_matrix: gas_model,
+                view_matrix: view,
+                projection_matrix: projection,
+                viewport_matrix: viewport,
+                time,
+                pipeline: color_pipeline,
+                noises: vec![&scene.gas_bands, &scene.gas_detail, &scene.gas_storms],
+                lights: &lights,
+                camera_position: eye,
+                occluders: &occluders,
+                shadow_map: shadow_map_ref,
+                atmosphere: None,
+                clouds: None,
+                craters: None,
+                ring: None,
+            },
+            vertices: &scene.sphere_lods.levels[gas_level],
+            shader: fragment_gas,
+            center: gas_pos,
+            radius: gas_scale * SPHERE_RADIUS,
+            blend: BlendMode::Replace,
+        });
+
+        if atmospheres {
+            let atmosphere = Atmosphere::hazy(gas_pos, gas_scale * SPHERE_RADIUS);
+            bodies.push(atmosphere_body(atmosphere, &scene.sphere_lods.levels[gas_level], Uniforms {
+                model_matrix: create_model_matrix(gas_pos, gas_scale * atmosphere.shell_scale, 0.0),
+                view_matrix: view,
+                projection_matrix: projection,
+                viewport_matrix: viewport,
+                time,
+                pipeline: color_pipeline,
+                noises: vec![],
+                lights: &lights,
+                camera_position: eye,
+                occluders: &occluders,
+                shadow_map: shadow_map_ref,
+                atmosphere: Some(atmosphere),
+                clouds: None,
+                craters: None,
+                ring: None,
+            }));
+        }
 
-            // Rings around gas giant (tilted ring in XZ plane) - always visible
-            let ring_rot_x = -std::f32::consts::FRAC_PI_2 * 0.9; // slight tilt
-            let uniforms_ring = Uniforms {
-                model_matrix: create_model_matrix_euler(gas_pos, ring_scale, ring_rot_x, rotation * 0.2, 0.0),
+        bodies.push(Body {
+            uniforms: Uniforms {
+                model_matrix: ring_model,
                 view_matrix: view,
                 projection_matrix: projection,
                 viewport_matrix: viewport,
                 time,
+                pipeline: color_pipeline,
                 noises: vec![],
+                lights: &lights,
+                camera_position: eye,
+                occluders: &occluders,
+                shadow_map: shadow_map_ref,
+                atmosphere: None,
+                clouds: None,
+                craters: None,
+                ring: Some(&scene.gas_ring.profile),
+            },
+            vertices: &scene.gas_ring.vertices,
+            shader: fragment_ring,
+            center: gas_pos,
+            radius: ring_scale * scene.gas_ring.outer,
+            blend: BlendMode::Alpha,
+        });
+    }
+
+    render_bodies(fb, &mut bodies, eye, culling, &mut state.debug);
+    fb.resolve_samples();
+    state.debug.compose(fb, line_style);
+
+    for (orbit, color) in &trails {
+        orbit.draw_trail(fb, tsec, *color, line_style, &view, &projection, &viewport);
+    }
+
+    // Halo y destellos de la estrella, atenuados por lo que tapan los planetas
+    if post && (mode == 0 || mode == 1) && (star_glow || lens_flare) {
+        let glow_color = color_pipeline.srgb(1.0, 0.78, 0.45);
+        if let Some(glow) = StarGlow::project(star_pos, star_scale * SPHERE_RADIUS, glow_color, eye, &view, &projection, &viewport) {
+            let visibility = glow.visible_fraction(fb);
+            if star_glow {
+                glow.draw_corona(fb, 1.0, visibility);
+            }
+            if lens_flare {
+                glow.draw_flare(fb, visibility, color_pipeline);
+            }
+        }
+    }
+
+    if post && bloom {
+        state.bloom.apply(fb);
+    }
+
+    fb.resolve(&debug_view.tone_mapping(tone_mapping), color_pipeline);
+    if antialiasing == AntiAliasing::Fxaa {
+        fxaa(fb);
+    }
+
+    // Overlay de LOD: círculo del radio proyectado, color y marcas según el nivel activo
+    if show_lod {
+        for (c, r, level) in &lod_overlays {
+            if let Some(sc) = project_point(*c, &view, &projection, &viewport) {
+                draw_lod_overlay(fb, sc, *r, *level);
+            }
+        }
+    }
+}
+
+// Fondo con imágenes desde SKYBOX: una carpeta con las seis caras (px.png, nx.png,
+// py.png, ny.png, pz.png, nz.png) o un panorama equirectangular. Si falla se avisa y
+// se sigue con el cielo procedural.
+fn load_skybox() -> Option<SkyMap> {
+    let path = std::env::var("SKYBOX").ok()?;
+    match SkyMap::load(std::path::Path::new(&path)) {
+        Ok(sky) => Some(sky),
+        Err(e) => {
+            eprintln!("No se pudo cargar el cielo {path}: {e}");
+            None
+        }
+    }
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    // Window
+    let w = 800usize;
+    let h = 800usize;
+    let mut window = Window::new("Lab5 - Planetas", w, h, WindowOptions::default())?;
+    let mut fb = Framebuffer::new(w, h);
+
+    // Camera
+    let eye = Vec3::new(0.0, 6.0, 22.0);
+
+    // Sphere LODs: icosferas de 80 a 5120 triángulos; si se pasa un OBJ por argumento se usa tal cual
+    let args: Vec<String> = std::env::args().collect();
+    let sphere_lods = if args.len() > 1 {
+        LodMesh::single(Obj::load(&args[1])?.get_vertex_array())
+    } else {
+        LodMesh::new(
+            (1..=4).map(|s| build_icosphere(SPHERE_RADIUS, s)).collect(),
+            vec![0.0, 12.0, 40.0, 120.0],
+        )
+    };
+
+    let scene = Scene::new(eye, sphere_lods);
+
+    let mut settings = Settings::default();
+    fb.set_samples(settings.antialiasing.samples());
+    let mut clock = Clock { time: 0.0, rotation: 0.0 };
+    let mut state = ViewState::new();
+    let screenshots = ScreenshotConfig::from_env(w, h);
+    let recording = RecordConfig::from_env();
+    let mut recorder: Option<Recorder> = None;
+    let mut timer = FrameTimer::new();
+    let (mut show_hud, mut show_help) = (true, false);
+
+    while window.is_open() && !window.is_key_down(Key::Escape) {
+        // grabando, el tiempo avanza un paso fijo por cuadro (el de los fps del archivo)
+        let step_ms = if recorder.is_some() { recording.step_ms() } else { 16.0 }; // ms aprox
+        clock.advance(step_ms);
+        timer.tick();
+        render_frame(&mut fb, &scene, &settings, &clock, &mut state);
+        if let Some(Err(e)) = recorder.as_mut().map(|r| r.push(&fb)) {
+            eprintln!("Error grabando, se detiene la grabación: {e}");
+            recorder = None;
+        }
+
+        // El HUD va después de grabar: ni la grabación ni las capturas lo llevan
+        if show_hud {
+            let info = HudInfo {
+                fps: timer.fps(),
+                frame_ms: timer.frame_ms(),
+                focus: match settings.mode {
+                    1 => "Estrella",
+                    2 => "Planeta rocoso",
+                    3 => "Gigante gaseoso",
+                    _ => "Sistema completo",
+                },
+                sim_speed: step_ms / timer.frame_ms(),
+                orbits: settings.animate_orbits,
+                render: format!(
+                    "{}  {} x{:.2}  luz {}  depuración: {}",
+                    settings.antialiasing.label(),
+                    settings.tone_mapping.operator.label(),
+                    settings.tone_mapping.exposure,
+                    settings.color_pipeline.label(),
+                    settings.debug_view.label(),
+                ),
+                recording: recorder.is_some(),
             };
-            render(&mut fb, &uniforms_ring, &ring_vertices, fragment_ring);
+            draw_hud(&mut fb, &info, show_help);
         }
 
         window.update_with_buffer(&fb.buffer, w, h)?;
 
         // Keys
         if window.is_key_pressed(Key::Key1, minifb::KeyRepeat::No) {
-            mode = 1;
+            settings.mode = 1;
         }
         if window.is_key_pressed(Key::Key2, minifb::KeyRepeat::No) {
-            mode = 2;
+            settings.mode = 2;
         }
         if window.is_key_pressed(Key::Key3, minifb::KeyRepeat::No) {
-            mode = 3;
+            settings.mode = 3;
         }
         if window.is_key_pressed(Key::Key0, minifb::KeyRepeat::No) {
-            mode = 0;
+            settings.mode = 0;
         }
         if window.is_key_pressed(Key::O, minifb::KeyRepeat::No) {
-            animate_orbits = !animate_orbits;
+            settings.animate_orbits = !settings.animate_orbits;
         }
-        if window.is_key_pressed(Key::S, minifb::KeyRepeat::No) {
-            let mut img = ImageBuffer::<Rgb<u8>, Vec<u8>>::new(w as u32, h as u32);
-            for y in 0..h {
-                for x in 0..w {
-                    let px = fb.buffer[y * w + x];
-                    let r = ((px >> 16) & 0xFF) as u8;
-                    let g = ((px >> 8) & 0xFF) as u8;
-                    let b = (px & 0xFF) as u8;
-                    img.put_pixel(x as u32, y as u32, Rgb([r, g, b]));
+        if window.is_key_pressed(Key::Q, minifb::KeyRepeat::No) {
+            settings.orbit_trails = !settings.orbit_trails;
+        }
+        if window.is_key_pressed(Key::W, minifb::KeyRepeat::No) {
+            settings.line_style = match settings.line_style {
+                LineStyle::Wu => LineStyle::Bresenham,
+                LineStyle::Bresenham => LineStyle::Wu,
+            };
+        }
+        if window.is_key_pressed(Key::L, minifb::KeyRepeat::No) {
+            settings.show_lod = !settings.show_lod;
+        }
+        if window.is_key_pressed(Key::C, minifb::KeyRepeat::No) {
+            settings.culling = !settings.culling;
+        }
+        if window.is_key_pressed(Key::H, minifb::KeyRepeat::No) {
+            settings.shadows = !settings.shadows;
+        }
+        if window.is_key_pressed(Key::M, minifb::KeyRepeat::No) {
+            settings.shadow_mapping = !settings.shadow_mapping;
+        }
+        if window.is_key_pressed(Key::LeftBracket, minifb::KeyRepeat::Yes) {
+            state.shadow_map.bias = (state.shadow_map.bias / 1.5).max(1e-5);
+        }
+        if window.is_key_pressed(Key::RightBracket, minifb::KeyRepeat::Yes) {
+            state.shadow_map.bias = (state.shadow_map.bias * 1.5).min(0.1);
+        }
+        if window.is_key_pressed(Key::P, minifb::KeyRepeat::No) {
+            state.shadow_map.pcf_radius = (state.shadow_map.pcf_radius + 1) % 4;
+        }
+        if window.is_key_pressed(Key::A, minifb::KeyRepeat::No) {
+            settings.atmospheres = !settings.atmospheres;
+        }
+        if window.is_key_pressed(Key::K, minifb::KeyRepeat::No) {
+            settings.clouds = !settings.clouds;
+        }
+        if window.is_key_pressed(Key::G, minifb::KeyRepeat::No) {
+            settings.star_glow = !settings.star_glow;
+        }
+        if window.is_key_pressed(Key::F, minifb::KeyRepeat::No) {
+            settings.lens_flare = !settings.lens_flare;
+        }
+        if window.is_key_pressed(Key::T, minifb::KeyRepeat::No) {
+            settings.tone_mapping.operator = settings.tone_mapping.operator.next();
+        }
+        if window.is_key_pressed(Key::Minus, minifb::KeyRepeat::Yes) {
+            settings.tone_mapping.exposure = (settings.tone_mapping.exposure / 1.1).max(0.05);
+        }
+        if window.is_key_pressed(Key::Equal, minifb::KeyRepeat::Yes) {
+            settings.tone_mapping.exposure = (settings.tone_mapping.exposure * 1.1).min(20.0);
+        }
+        if window.is_key_pressed(Key::B, minifb::KeyRepeat::No) {
+            settings.bloom = !settings.bloom;
+        }
+        if window.is_key_pressed(Key::Comma, minifb::KeyRepeat::Yes) {
+            state.bloom.threshold = (state.bloom.threshold - 0.05).max(0.0);
+        }
+        if window.is_key_pressed(Key::Period, minifb::KeyRepeat::Yes) {
+            state.bloom.threshold = (state.bloom.threshold + 0.05).min(10.0);
+        }
+        if window.is_key_pressed(Key::Semicolon, minifb::KeyRepeat::Yes) {
+            state.bloom.intensity = (state.bloom.intensity - 0.05).max(0.0);
+        }
+        if window.is_key_pressed(Key::Apostrophe, minifb::KeyRepeat::Yes) {
+            state.bloom.intensity = (state.bloom.intensity + 0.05).min(5.0);
+        }
+        if window.is_key_pressed(Key::N, minifb::KeyRepeat::No) {
+            settings.stars = !settings.stars;
+        }
+        if window.is_key_pressed(Key::V, minifb::KeyRepeat::No) {
+            settings.galactic_band = !settings.galactic_band;
+        }
+        if window.is_key_pressed(Key::U, minifb::KeyRepeat::No) {
+            settings.skybox = !settings.skybox;
+        }
+        if window.is_key_pressed(Key::X, minifb::KeyRepeat::No) {
+            settings.antialiasing = settings.antialiasing.next();
+            fb.set_samples(settings.antialiasing.samples());
+        }
+        if window.is_key_pressed(Key::D, minifb::KeyRepeat::No) {
+            settings.debug_view = settings.debug_view.next();
+        }
+        if window.is_key_pressed(Key::I, minifb::KeyRepeat::No) {
+            show_hud = !show_hud;
+        }
+        if window.is_key_pressed(Key::F1, minifb::KeyRepeat::No) {
+            show_hud = true;
+            show_help = !show_help;
+        }
+        if window.is_key_pressed(Key::E, minifb::KeyRepeat::No) {
+            settings.color_pipeline = settings.color_pipeline.next();
+        }
+        if window.is_key_pressed(Key::R, minifb::KeyRepeat::No) {
+            match recorder.take() {
+                Some(rec) => {
+                    let (path, frames) = rec.finish();
+                    println!("Grabación guardada en {} ({frames} cuadros)", path.display());
                 }
+                None => match Recorder::start(&recording) {
+                    Ok(rec) => recorder = Some(rec),
+                    Err(e) => eprintln!("No se pudo empezar la grabación: {e}"),
+                },
             }
-            let _ = img.save("screenshot.png");
+        }
+        if window.is_key_pressed(Key::S, minifb::KeyRepeat::No) {
+            // los LOD de la captura no deben alterar la histéresis de la ventana
+            let lods = state.lods.clone();
+            match screenshots.capture(settings.antialiasing.samples(), |shot| render_frame(shot, &scene, &settings, &clock, &mut state)) {
+                Ok(path) => println!("Captura guardada en {}", path.display()),
+                Err(e) => eprintln!("No se pudo guardar la captura: {e}"),
+            }
+            state.lods = lods;
         }
     }
 
     Ok(())
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Con los planetas a lados opuestos de la estrella, el mapa de sombras tiene
+    // casters delante y detrás de la luz; el cuadro debe terminar igual
+    #[test]
+    fn frame_with_planets_on_opposite_sides_of_the_star() {
+        let sphere_lods = LodMesh::new((1..=2).map(|s| build_icosphere(SPHERE_RADIUS, s)).collect(), vec![0.0, 12.0]);
+        let scene = Scene::new(Vec3::new(0.0, 6.0, 22.0), sphere_lods);
+        let settings = Settings { animate_orbits: true, ..Settings::default() };
+        let star = Vec3::new(-8.0, 0.0, 0.0);
+        let (rocky, gas) = (Orbit::circular(star, 6.0, 0.8), Orbit::circular(star, 12.0, 0.4));
+        // 6.5 s: casi opuestos; π / 0.4 s: exactamente opuestos
+        for t in [6.5, std::f32::consts::PI / 0.4] {
+            assert!((rocky.position(t) - star).dot(&(gas.position(t) - star)) < 0.0);
+            let mut fb = Framebuffer::new(90, 160);
+            fb.set_samples(settings.antialiasing.samples());
+            let clock = Clock { time: t * 1000.0, rotation: 0.0 };
+            render_frame(&mut fb, &scene, &settings, &clock, &mut ViewState::new());
+            assert!(fb.buffer.iter().any(|&px| px != 0));
+        }
+    }
+}
@@ -1,5 +1,4 @@
 use nalgebra_glm::{Vec2, Vec3};
-use tobj;
 use crate::vertex::Vertex;
 
 pub struct Obj {
@@ -40,7 +39,8 @@ impl Obj {
                 let i = idx as usize;
                 let pos = mesh.vertices[i];
                 let normal = mesh.normals.get(i).copied().unwrap_or_else(|| pos.normalize());
-                v.push(Vertex::new(pos, normal));
+                let uv = mesh.texcoords.get(i).copied().unwrap_or_else(Vec2::zeros);
+                v.push(Vertex::new(pos, normal).with_tex_coords(uv));
             }
         }
         v
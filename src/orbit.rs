@@ -0,0 +1,49 @@
+use nalgebra_glm::{Mat4, Vec3};
+use crate::{color::Color, framebuffer::Framebuffer, line::{draw_line, project_segment, LineStyle}};
+
+// Segmentos de la estela por vuelta
+const TRAIL_SEGMENTS: usize = 128;
+
+// Órbita circular en el plano XZ alrededor de `center`, con un vaivén vertical
+// opcional (la luna): y = bob * sin(bob_rate * ángulo)
+#[derive(Debug, Clone, Copy)]
+pub struct Orbit {
+    pub center: Vec3,
+    pub radius: f32,
+    // rad/s de simulación
+    pub angular_speed: f32,
+    pub bob: f32,
+    pub bob_rate: f32,
+}
+
+impl Orbit {
+    pub fn circular(center: Vec3, radius: f32, angular_speed: f32) -> Self {
+        Self { center, radius, angular_speed, bob: 0.0, bob_rate: 0.0 }
+    }
+
+    // Posición en el instante `t` (segundos de simulación)
+    pub fn position(&self, t: f32) -> Vec3 {
+        self.at_angle(t * self.angular_speed)
+    }
+
+    fn at_angle(&self, a: f32) -> Vec3 {
+        self.center + Vec3::new(a.cos() * self.radius, self.bob * (a * self.bob_rate).sin(), a.sin() * self.radius)
+    }
+
+    // Estela de una vuelta completa que termina en la posición actual, más tenue
+    // cuanto más atrás en el tiempo. Se tapa con los cuerpos (prueba de profundidad).
+    #[allow(clippy::too_many_arguments)]
+    pub fn draw_trail(&self, framebuffer: &mut Framebuffer, t: f32, color: Color, style: LineStyle, view: &Mat4, projection: &Mat4, viewport: &Mat4) {
+        let now = t * self.angular_speed;
+        let step = std::f32::consts::TAU / TRAIL_SEGMENTS as f32 * self.angular_speed.signum();
+        let mut prev = self.at_angle(now - step * TRAIL_SEGMENTS as f32);
+        for i in 1..=TRAIL_SEGMENTS {
+            let p = self.at_angle(now - step * (TRAIL_SEGMENTS - i) as f32);
+            if let Some((a, b)) = project_segment(prev, p, view, projection, viewport) {
+                let age = i as f32 / TRAIL_SEGMENTS as f32;
+                draw_line(framebuffer, a, b, color, 0.1 + 0.6 * age, style);
+            }
+            prev = p;
+        }
+    }
+}
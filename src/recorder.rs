@@ -0,0 +1,107 @@
+use std::fs::File;
+use std::path::PathBuf;
+use image::codecs::gif::{GifEncoder, Repeat};
+use image::{Delay, DynamicImage, Frame};
+use crate::{framebuffer::Framebuffer, screenshot::{buffer_image, timestamp}};
+
+// Formato de salida de una grabación
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RecordFormat {
+    // carpeta con frame-00000.png, frame-00001.png, ... (para montar en un editor)
+    PngSequence,
+    // GIF animado en bucle, directo para presentaciones
+    Gif,
+}
+
+// Grabación de la animación. Se configura con variables de entorno:
+//   RECORD_DIR     carpeta de salida (por defecto `recordings`)
+//   RECORD_FORMAT  `gif` o `png` (por defecto gif)
+//   RECORD_FPS     cuadros por segundo del resultado (por defecto 25)
+pub struct RecordConfig {
+    pub dir: PathBuf,
+    pub format: RecordFormat,
+    pub fps: u32,
+}
+
+impl RecordConfig {
+    pub fn from_env() -> Self {
+        let dir = std::env::var("RECORD_DIR").unwrap_or_else(|_| "recordings".to_string());
+        let format = match std::env::var("RECORD_FORMAT").as_deref() {
+            Ok("png") => RecordFormat::PngSequence,
+            Ok("gif") | Err(_) => RecordFormat::Gif,
+            Ok(other) => {
+                eprintln!("RECORD_FORMAT inválido ({other}), se usa gif");
+                RecordFormat::Gif
+            }
+        };
+        let fps = match std::env::var("RECORD_FPS") {
+            Ok(n) => n.trim().parse().ok().filter(|n| (1..=100).contains(n)).unwrap_or_else(|| {
+                eprintln!("RECORD_FPS inválido ({n}), se usa 25");
+                25
+            }),
+            Err(_) => 25,
+        };
+        Self { dir: PathBuf::from(dir), format, fps }
+    }
+
+    // Paso fijo de simulación por cuadro grabado (ms): cada cuadro del archivo avanza
+    // lo mismo aunque dibujarlo y codificarlo tarde más que eso en tiempo real
+    pub fn step_ms(&self) -> f32 {
+        1000.0 / self.fps as f32
+    }
+}
+
+enum Output {
+    Frames(PathBuf),
+    Gif(GifEncoder<File>),
+}
+
+pub struct Recorder {
+    output: Output,
+    path: PathBuf,
+    delay_ms: u32,
+    frames: usize,
+}
+
+impl Recorder {
+    // Crea la carpeta o el archivo de salida con la fecha en el nombre
+    pub fn start(config: &RecordConfig) -> Result<Self, image::ImageError> {
+        std::fs::create_dir_all(&config.dir)?;
+        let name = format!("recording-{}", timestamp());
+        let (output, path) = match config.format {
+            RecordFormat::PngSequence => {
+                let path = config.dir.join(name);
+                std::fs::create_dir_all(&path)?;
+                (Output::Frames(path.clone()), path)
+            }
+            RecordFormat::Gif => {
+                let path = config.dir.join(format!("{name}.gif"));
+                // velocidad 10 de NeuQuant: buena paleta sin frenar demasiado la grabación
+                let mut encoder = GifEncoder::new_with_speed(File::create(&path)?, 10);
+                encoder.set_repeat(Repeat::Infinite)?;
+                (Output::Gif(encoder), path)
+            }
+        };
+        Ok(Self { output, path, delay_ms: 1000 / config.fps, frames: 0 })
+    }
+
+    // Añade la imagen ya resuelta del framebuffer como siguiente cuadro
+    pub fn push(&mut self, framebuffer: &Framebuffer) -> Result<(), image::ImageError> {
+        let image = buffer_image(framebuffer);
+        match &mut self.output {
+            Output::Frames(dir) => image.save(dir.join(format!("frame-{:05}.png", self.frames)))?,
+            Output::Gif(encoder) => {
+                let rgba = DynamicImage::ImageRgb8(image).into_rgba8();
+                encoder.encode_frame(Frame::from_parts(rgba, 0, 0, Delay::from_numer_denom_ms(self.delay_ms, 1)))?;
+            }
+        }
+        self.frames += 1;
+        Ok(())
+    }
+
+    // Cierra el archivo (el GIF se termina al soltar el encoder); devuelve la ruta
+    // y el número de cuadros
+    pub fn finish(self) -> (PathBuf, usize) {
+        (self.path, self.frames)
+    }
+}
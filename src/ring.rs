@@ -1,14 +1,63 @@
-use nalgebra_glm::Vec3;
-use crate::vertex::Vertex;
+use nalgebra_glm::{Vec2, Vec3};
+use crate::{shaders::smoothstep, texture::StripTexture, vertex::Vertex};
+
+// Anillo de un cuerpo: radios en unidades del modelo (se escala con el planeta),
+// su malla y el perfil radial de color/opacidad.
+pub struct Ring {
+    pub inner: f32,
+    pub outer: f32,
+    pub vertices: Vec<Vertex>,
+    pub profile: RingProfile,
+}
+
+impl Ring {
+    pub fn new(inner: f32, outer: f32, profile: RingProfile) -> Self {
+        Self { inner, outer, vertices: build_ring(inner, outer, 128), profile }
+    }
+}
+
+// Perfil radial según u (0 = borde interior, 1 = exterior)
+pub enum RingProfile {
+    // bandas y huecos de `ring_density` con la paleta del shader
+    Procedural,
+    // tira de textura con color y opacidad (p. ej. un perfil real de Saturno)
+    Strip(StripTexture),
+}
+
+impl RingProfile {
+    // Carga la tira o, si falla, avisa y usa el perfil procedural
+    pub fn load_or_procedural(path: &str) -> Self {
+        match StripTexture::load(path) {
+            Ok(strip) => RingProfile::Strip(strip),
+            Err(e) => {
+                eprintln!("No se pudo cargar el perfil del anillo {path}: {e}; se usa el procedural");
+                RingProfile::Procedural
+            }
+        }
+    }
+
+    // Opacidad (0..1) en la coordenada radial `u`
+    pub fn density(&self, u: f32) -> f32 {
+        match self {
+            RingProfile::Procedural => ring_density(u),
+            RingProfile::Strip(_) if !(0.0..=1.0).contains(&u) => 0.0,
+            RingProfile::Strip(strip) => strip.sample(u).a,
+        }
+    }
+}
 
 // Build a flat annulus in XY plane centered at origin.
 // Returns a vertex array where every 3 vertices form a triangle.
+// UV: u radial (0 = borde interior, 1 = exterior), v angular (0..1).
 pub fn build_ring(inner_radius: f32, outer_radius: f32, segments: usize) -> Vec<Vertex> {
     let segs = segments.max(3);
     let mut verts: Vec<Vertex> = Vec::with_capacity(segs * 6);
     let n = Vec3::new(0.0, 0.0, 1.0);
 
+    let (v_step, inner_uv, outer_uv) = (1.0 / segs as f32, 0.0, 1.0);
+
     for i in 0..segs {
+        let (v0, v1) = (i as f32 * v_step, (i + 1) as f32 * v_step);
         let t0 = (i as f32) * std::f32::consts::TAU / (segs as f32);
         let t1 = ((i + 1) as f32) * std::f32::consts::TAU / (segs as f32);
 
@@ -21,14 +70,30 @@ pub fn build_ring(inner_radius: f32, outer_radius: f32, segments: usize) -> Vec<
         let outer1 = Vec3::new(c1 * outer_radius, s1 * outer_radius, 0.0);
 
         // Triangle 1: outer0, inner0, inner1
-        verts.push(Vertex::new(outer0, n));
-        verts.push(Vertex::new(inner0, n));
-        verts.push(Vertex::new(inner1, n));
+        verts.push(Vertex::new(outer0, n).with_tex_coords(Vec2::new(outer_uv, v0)));
+        verts.push(Vertex::new(inner0, n).with_tex_coords(Vec2::new(inner_uv, v0)));
+        verts.push(Vertex::new(inner1, n).with_tex_coords(Vec2::new(inner_uv, v1)));
         // Triangle 2: outer0, inner1, outer1
-        verts.push(Vertex::new(outer0, n));
-        verts.push(Vertex::new(inner1, n));
-        verts.push(Vertex::new(outer1, n));
+        verts.push(Vertex::new(outer0, n).with_tex_coords(Vec2::new(outer_uv, v0)));
+        verts.push(Vertex::new(inner1, n).with_tex_coords(Vec2::new(inner_uv, v1)));
+        verts.push(Vertex::new(outer1, n).with_tex_coords(Vec2::new(outer_uv, v1)));
     }
 
     verts
 }
+
+// Densidad óptica (0..1) según el radio normalizado `t` (0 = borde interior,
+// 1 = exterior), al estilo de Saturno: anillo C tenue, B denso, división de
+// Cassini, anillo A con la división de Encke y bandas finas encima.
+pub fn ring_density(t: f32) -> f32 {
+    if !(0.0..=1.0).contains(&t) {
+        return 0.0;
+    }
+    let step = |d: f32, target: f32, at: f32| d + (target - d) * smoothstep(at - 0.01, at + 0.01, t);
+    let mut d = 0.25; // C
+    d = step(d, 0.95, 0.2); // B
+    d = step(d, 0.04, 0.55); // Cassini
+    d = step(d, 0.65, 0.62); // A
+    d *= 1.0 - 0.9 * (1.0 - smoothstep(0.004, 0.01, (t - 0.88).abs())); // Encke
+    d * (0.8 + 0.2 * (t * 157.0).sin() * (t * 41.0).sin())
+}
@@ -0,0 +1,207 @@
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use image::{ImageBuffer, Rgb, RgbImage};
+use crate::{color::{linear_to_srgb_byte, srgb_to_linear}, framebuffer::Framebuffer};
+
+// Muestras que se dibujan como máximo en una captura (píxeles x supersampling² x
+// MSAA): unos 400 MB entre color HDR, profundidad y muestras
+const MAX_RENDER_SAMPLES: usize = 4096 * 4096;
+
+// Capturas en alta resolución: el cuadro se vuelve a dibujar fuera de pantalla a
+// `width * supersample` x `height * supersample` y se reduce promediando bloques.
+// Se configura con variables de entorno:
+//   SCREENSHOT_DIR          carpeta de salida (por defecto `screenshots`)
+//   SCREENSHOT_SIZE         resolución final, p. ej. `3840x2160` (por defecto 2x la ventana)
+//   SCREENSHOT_SUPERSAMPLE  muestras por eje (por defecto 2)
+// Si el total pasa de MAX_RENDER_SAMPLES se baja el supersampling y, si no basta,
+// la resolución (avisando por consola).
+pub struct ScreenshotConfig {
+    pub dir: PathBuf,
+    pub width: usize,
+    pub height: usize,
+    pub supersample: usize,
+}
+
+impl ScreenshotConfig {
+    pub fn from_env(window_width: usize, window_height: usize) -> Self {
+        let dir = std::env::var("SCREENSHOT_DIR").unwrap_or_else(|_| "screenshots".to_string());
+        let (width, height) = match std::env::var("SCREENSHOT_SIZE") {
+            Ok(size) => parse_size(&size).unwrap_or_else(|| {
+                eprintln!("SCREENSHOT_SIZE inválido ({size}), se usa 2x la ventana");
+                (window_width * 2, window_height * 2)
+            }),
+            Err(_) => (window_width * 2, window_height * 2),
+        };
+        let supersample = match std::env::var("SCREENSHOT_SUPERSAMPLE") {
+            Ok(n) => n.trim().parse().ok().filter(|n| (1..=8).contains(n)).unwrap_or_else(|| {
+                eprintln!("SCREENSHOT_SUPERSAMPLE inválido ({n}), se usa 2");
+                2
+            }),
+            Err(_) => 2,
+        };
+        Self { dir: PathBuf::from(dir), width, height, supersample }
+    }
+
+    // Dibuja con `render` en un framebuffer del tamaño supersampleado, reduce y guarda
+    // como `<dir>/screenshot-<fecha>.png`. Con supersampling el MSAA sobra y se
+    // desactiva (además multiplicaría la memoria); sin él se usan `samples`.
+    pub fn capture(&self, samples: usize, render: impl FnOnce(&mut Framebuffer)) -> Result<PathBuf, image::ImageError> {
+        let (width, height, supersample, samples) = fit_budget(self.width, self.height, self.supersample, samples);
+        if (width, height, supersample) != (self.width, self.height, self.supersample) {
+            eprintln!(
+                "Captura de {}x{} x{} demasiado grande, se reduce a {width}x{height} x{supersample}",
+                self.width, self.height, self.supersample
+            );
+        }
+        let mut fb = Framebuffer::new(width * supersample, height * supersample);
+        fb.set_samples(samples);
+        fb.supersample = supersample;
+        render(&mut fb);
+        let image = downsample(&fb, supersample);
+        save(&image, &self.dir, "screenshot")
+    }
+}
+
+// Resolución, supersampling y muestras MSAA que caben en MAX_RENDER_SAMPLES. Con
+// supersampling el MSAA se desactiva; se baja primero el supersampling, después se
+// quita el MSAA y por último se escala la resolución manteniendo la proporción.
+fn fit_budget(width: usize, height: usize, supersample: usize, samples: usize) -> (usize, usize, usize, usize) {
+    let cost = |w: usize, h: usize, ss: usize, n: usize| w * h * ss * ss * n;
+    for ss in (2..=supersample).rev() {
+        if cost(width, height, ss, 1) <= MAX_RENDER_SAMPLES {
+            return (width, height, ss, 1);
+        }
+    }
+    if cost(width, height, 1, samples) <= MAX_RENDER_SAMPLES {
+        return (width, height, 1, samples);
+    }
+    let scale = (MAX_RENDER_SAMPLES as f64 / cost(width, height, 1, 1) as f64).sqrt().min(1.0);
+    let (w, h) = (((width as f64 * scale) as usize).max(1), ((height as f64 * scale) as usize).max(1));
+    (w, h, 1, 1)
+}
+
+// "3840x2160" -> (3840, 2160)
+fn parse_size(s: &str) -> Option<(usize, usize)> {
+    let (w, h) = s.trim().split_once(['x', 'X'])?;
+    let (w, h) = (w.trim().parse().ok()?, h.trim().parse().ok()?);
+    (w > 0 && h > 0).then_some((w, h))
+}
+
+// La imagen ya resuelta tal cual, sin reducir
+pub fn buffer_image(framebuffer: &Framebuffer) -> RgbImage {
+    ImageBuffer::from_fn(framebuffer.width as u32, framebuffer.height as u32, |x, y| {
+        let px = framebuffer.buffer[y as usize * framebuffer.width + x as usize];
+        Rgb([(px >> 16) as u8, (px >> 8) as u8, px as u8])
+    })
+}
+
+// Promedia bloques de `factor` x `factor` píxeles de la imagen resuelta. Los bytes
+// son sRGB en cualquier pipeline: se promedia en luz lineal (curva exacta de ida y
+// vuelta) para que los bordes no queden más oscuros de la cuenta.
+pub fn downsample(framebuffer: &Framebuffer, factor: usize) -> RgbImage {
+    let (w, h) = (framebuffer.width / factor, framebuffer.height / factor);
+    let weight = 1.0 / (factor * factor) as f32;
+    let mut image = ImageBuffer::new(w as u32, h as u32);
+    for y in 0..h {
+        for x in 0..w {
+            let mut sum = [0.0f32; 3];
+            for sy in 0..factor {
+                for sx in 0..factor {
+                    let px = framebuffer.buffer[(y * factor + sy) * framebuffer.width + x * factor + sx];
+                    for (c, shift) in sum.iter_mut().zip([16, 8, 0]) {
+                        *c += srgb_to_linear(((px >> shift) & 0xFF) as f32 / 255.0);
+                    }
+                }
+            }
+            let [r, g, b] = sum.map(|c| linear_to_srgb_byte(c * weight) as u8);
+            image.put_pixel(x as u32, y as u32, Rgb([r, g, b]));
+        }
+    }
+    image
+}
+
+// Guarda en `dir` (creándola si hace falta) con la fecha en el nombre para no pisar
+// capturas anteriores
+pub fn save(image: &RgbImage, dir: &Path, prefix: &str) -> Result<PathBuf, image::ImageError> {
+    std::fs::create_dir_all(dir)?;
+    let path = dir.join(format!("{prefix}-{}.png", timestamp()));
+    image.save(&path)?;
+    Ok(path)
+}
+
+// Fecha y hora UTC como `AAAAMMDD-HHMMSS-mmm`
+pub fn timestamp() -> String {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+    let secs = now.as_secs();
+    let (days, rem) = (secs / 86_400, secs % 86_400);
+    let (year, month, day) = civil_from_days(days as i64);
+    format!(
+        "{year:04}{month:02}{day:02}-{:02}{:02}{:02}-{:03}",
+        rem / 3600,
+        rem % 3600 / 60,
+        rem % 60,
+        now.subsec_millis()
+    )
+}
+
+// Días desde 1970-01-01 a fecha del calendario gregoriano (algoritmo de H. Hinnant)
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn budget_keeps_requests_that_fit() {
+        assert_eq!(fit_budget(1600, 1600, 2, 4), (1600, 1600, 2, 1));
+        assert_eq!(fit_budget(800, 800, 1, 4), (800, 800, 1, 4));
+    }
+
+    #[test]
+    fn budget_lowers_supersampling_then_msaa() {
+        assert_eq!(fit_budget(2560, 1440, 4, 4), (2560, 1440, 2, 1));
+        assert_eq!(fit_budget(3840, 2160, 2, 4), (3840, 2160, 1, 1));
+    }
+
+    #[test]
+    fn budget_scales_resolution_keeping_aspect() {
+        let (w, h, ss, n) = fit_budget(16_000, 9_000, 8, 4);
+        assert_eq!((ss, n), (1, 1));
+        assert!(w * h <= MAX_RENDER_SAMPLES);
+        assert!((w as f32 / h as f32 - 16.0 / 9.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn parse_size_accepts_either_separator_and_spaces() {
+        assert_eq!(parse_size("3840x2160"), Some((3840, 2160)));
+        assert_eq!(parse_size("1920X1080"), Some((1920, 1080)));
+        assert_eq!(parse_size(" 10 x 20 "), Some((10, 20)));
+    }
+
+    #[test]
+    fn parse_size_rejects_invalid_sizes() {
+        for s in ["0x5", "5x0", "abc", "10x", "x10", "-1x10", "10x20x30"] {
+            assert_eq!(parse_size(s), None, "{s}");
+        }
+    }
+
+    #[test]
+    fn civil_from_days_matches_known_dates() {
+        assert_eq!(civil_from_days(0), (1970, 1, 1));
+        assert_eq!(civil_from_days(-1), (1969, 12, 31));
+        assert_eq!(civil_from_days(11_016), (2000, 2, 29));
+        assert_eq!(civil_from_days(19_723), (2024, 1, 1));
+        assert_eq!(civil_from_days(19_782), (2024, 2, 29));
+    }
+}
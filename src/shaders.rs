@@ -1,9 +1,21 @@
-use crate::{color::Color, fragment::Fragment, vertex::Vertex, Uniforms};
+use crate::{color::Color, fragment::Fragment, light::{shade, Material, Specular}, ring::RingProfile, vertex::Vertex, Uniforms};
 use nalgebra_glm::{mat4_to_mat3, Vec3, Vec4, Mat3};
 
+// Materiales: el agua tiene brillo del sol, el gigante gaseoso un lustre suave
+const ROCKY_MATERIAL: Material = Material { ambient: 0.2, diffuse: 0.8, specular: 0.9, model: Specular::BlinnPhong { shininess: 64.0 } };
+const GAS_MATERIAL: Material = Material { ambient: 0.2, diffuse: 0.8, specular: 0.25, model: Specular::Ggx { roughness: 0.6, f0: 0.04 } };
+const MOON_MATERIAL: Material = Material::matte();
+const CLOUD_MATERIAL: Material = Material::matte();
+const RING_MATERIAL: Material = Material::matte();
+
+// Intensidad del relieve (bump) que sale del gradiente de la altura
+const ROCKY_BUMP: f32 = 1.5;
+const MOON_BUMP: f32 = 1.0;
+
 pub fn vertex_shader(vertex: &Vertex, uniforms: &Uniforms) -> Vertex {
     let pos4 = Vec4::new(vertex.position.x, vertex.position.y, vertex.position.z, 1.0);
-    let clip = uniforms.projection_matrix * uniforms.view_matrix * uniforms.model_matrix * pos4;
+    let world = uniforms.model_matrix * pos4;
+    let clip = uniforms.projection_matrix * uniforms.view_matrix * world;
     let ndc = Vec4::new(clip.x / clip.w, clip.y / clip.w, clip.z / clip.w, 1.0);
     let screen = uniforms.viewport_matrix * ndc;
 
@@ -14,8 +26,10 @@ pub fn vertex_shader(vertex: &Vertex, uniforms: &Uniforms) -> Vertex {
     Vertex {
         position: vertex.position,
         normal: vertex.normal,
+        tex_coords: vertex.tex_coords,
         color: vertex.color,
         transformed_position: Vec3::new(screen.x, screen.y, screen.z),
+        world_position: Vec3::new(world.x, world.y, world.z),
         transformed_normal,
     }
 }
@@ -28,15 +42,15 @@ pub fn fragment_star(fragment: &Fragment, uniforms: &Uniforms) -> Color {
 
     // Layer 1: base plasma color (core->hot) modulated by low-freq noise (color-affecting)
     let n_base = uniforms.noises[0].get_noise_3d(p.x * 2.2 + t * 0.6, p.y * 2.2, p.z * 2.2 - t * 0.6);
-    let core = Color::from_float(1.0, 0.9, 0.35);
-    let hot  = Color::from_float(1.0, 0.42, 0.05);
+    let core = uniforms.pipeline.srgb(1.0, 0.9, 0.35);
+    let hot  = uniforms.pipeline.srgb(1.0, 0.42, 0.05);
     let mut col = lerp_color(core, hot, ((n_base + 1.0) * 0.5).clamp(0.0, 1.0));
 
     // Layer 2: sunspots (dark patches) via thresholded noise (color-affecting)
     if uniforms.noises.len() > 1 {
         let n_spot = uniforms.noises[1].get_noise_3d(p.x * 3.0 - t * 0.4, p.y * 3.0, p.z * 3.0 + t * 0.3);
         let mask = smoothstep(0.2, 0.5, n_spot.abs()); // manchas más grandes y notorias
-        let dark = Color::from_float(0.18, 0.10, 0.04);
+        let dark = uniforms.pipeline.srgb(0.18, 0.10, 0.04);
         col = lerp_color(col, dark, mask * 0.65);
     }
 
@@ -44,12 +58,12 @@ pub fn fragment_star(fragment: &Fragment, uniforms: &Uniforms) -> Color {
     if uniforms.noises.len() > 2 {
         let n_gran = uniforms.noises[2].get_noise_3d(p.x * 24.0, p.y * 24.0, p.z * 24.0);
         let brt = smoothstep(0.35, 0.9, (n_gran + 1.0) * 0.5);
-        col = lerp_color(col, Color::from_float(1.0, 0.97, 0.78), brt * 0.35);
+        col = lerp_color(col, uniforms.pipeline.srgb(1.0, 0.97, 0.78), brt * 0.35);
     }
 
     // Layer 4: rim glow (brighter edge) (color-affecting)
     let rim = (1.0 - fragment.normal.z.abs()).clamp(0.0, 1.0).powf(1.3);
-    col = lerp_color(col, Color::from_float(1.0, 0.95, 0.8), rim * 0.55);
+    col = lerp_color(col, uniforms.pipeline.srgb(1.0, 0.95, 0.8), rim * 0.55);
 
     let pulse = 0.9 + (t * 0.6).sin() * 0.1;
     col * pulse
@@ -58,21 +72,27 @@ pub fn fragment_star(fragment: &Fragment, uniforms: &Uniforms) -> Color {
 // Planeta rocoso: capas (base+detalle) para altura y paletas tierra/roca/hielo
 pub fn fragment_rocky(fragment: &Fragment, uniforms: &Uniforms) -> Color {
     let p = fragment.vertex_position;
-    let base = uniforms.noises[0].get_noise_3d(p.x * 0.7, p.y * 0.7, p.z * 0.7);
-    let detail = uniforms.noises[1].get_noise_3d(p.x * 2.0, p.y * 2.0, p.z * 2.0);
-    let h = ((base * 0.7 + detail * 0.3) + 1.0) * 0.5; // 0..1 height
+    let height = |p: Vec3| {
+        let base = uniforms.noises[0].get_noise_3d(p.x * 0.7, p.y * 0.7, p.z * 0.7);
+        let detail = uniforms.noises[1].get_noise_3d(p.x * 2.0, p.y * 2.0, p.z * 2.0);
+        ((base * 0.7 + detail * 0.3) + 1.0) * 0.5 // 0..1 height
+    };
+    let h = height(p);
     let lat = ((p.y + 1.0) * 0.5).clamp(0.0, 1.0); // 0=pole S, 1=pole N, 0.5=ecuador
 
     // Layer 1: Ocean vs tierra por nivel del mar (color-affecting)
     let sea = 0.52;
     let shore = 0.03;
-    let ocean_deep = Color::from_float(0.05, 0.10, 0.30);
-    let ocean_shallow = Color::from_float(0.10, 0.45, 0.75);
+    let ocean_deep = uniforms.pipeline.srgb(0.05, 0.10, 0.30);
+    let ocean_shallow = uniforms.pipeline.srgb(0.10, 0.45, 0.75);
     let mut col;
+    let mut water = 0.0; // máscara especular: sólo el océano refleja el sol
+    let mut cities = 0.0; // máscara emisiva de luces nocturnas
     if h < sea {
         let d = ((sea - h) / shore).clamp(0.0, 1.0);
         // cerca de la costa más claro (shallow), profundo más oscuro
         col = lerp_color(ocean_shallow, ocean_deep, d);
+        water = smoothstep(0.0, 0.5, d);
     } else {
         // Tierra
         let elev = ((h - sea) / (1.0 - sea)).clamp(0.0, 1.0);
@@ -85,35 +105,53 @@ pub fn fragment_rocky(fragment: &Fragment, uniforms: &Uniforms) -> Color {
         let desert_factor = smoothstep(0.4, 0.8, (1.0 - moisture) * temp);
         let grass_factor = smoothstep(0.3, 0.7, moisture * temp) * (1.0 - elev * 0.7);
 
-        let desert = Color::from_float(0.73, 0.64, 0.40);
-        let grass = Color::from_float(0.20, 0.50, 0.25);
-        let dirt  = Color::from_float(0.42, 0.33, 0.26);
+        let desert = uniforms.pipeline.srgb(0.73, 0.64, 0.40);
+        let grass = uniforms.pipeline.srgb(0.20, 0.50, 0.25);
+        let dirt  = uniforms.pipeline.srgb(0.42, 0.33, 0.26);
         let land_base = lerp_color(dirt, grass, grass_factor);
         let land_biome = lerp_color(land_base, desert, desert_factor * 0.8);
 
         // Layer 3: Montañas (color-affecting)
-        let mountain = Color::from_float(0.62, 0.60, 0.58);
+        let mountain = uniforms.pipeline.srgb(0.62, 0.60, 0.58);
         let m_fac = (elev * 1.3).clamp(0.0, 1.0).powf(1.6);
         let land = lerp_color(land_biome, mountain, m_fac);
 
         col = land;
 
         // Layer 4: Hielo/nieve en latitudes altas o gran altitud (color-affecting)
-        let snow = Color::from_float(0.96, 0.97, 1.0);
+        let snow = uniforms.pipeline.srgb(0.96, 0.97, 1.0);
         let polar = smoothstep(0.65, 0.9, (lat - 0.5).abs() * 2.0);
         let snow_alt = smoothstep(0.7, 0.9, elev);
         let s_fac = (polar * 0.7 + snow_alt * 0.6).clamp(0.0, 1.0);
         col = lerp_color(col, snow, s_fac);
 
-        // Extra: nubes delgadas (ligero blanqueo) - usa cuarta textura si hay
+        // Layer 5: ciudades en pastizales y desiertos, no en montaña ni hielo
         if uniforms.noises.len() > 3 {
-            let n_cloud = uniforms.noises[3].get_noise_3d(p.x * 4.0, p.y * 4.0, p.z * 4.0);
-            let c = smoothstep(0.55, 0.75, (n_cloud + 1.0) * 0.5);
-            col = lerp_color(col, Color::from_float(1.0, 1.0, 1.0), c * 0.20);
+            let habitable = (grass_factor + desert_factor * 0.5).clamp(0.0, 1.0) * (1.0 - m_fac) * (1.0 - s_fac);
+            let region = smoothstep(0.05, 0.35, uniforms.noises[3].get_noise_3d(p.x, p.y, p.z));
+            let n_town = uniforms.noises[3].get_noise_3d(p.x * 6.0 + 17.0, p.y * 6.0, p.z * 6.0);
+            let towns = smoothstep(0.15, 0.45, n_town);
+            cities = habitable * (region * 0.5 + towns).clamp(0.0, 1.0) * smoothstep(0.0, 0.05, h - sea);
         }
     }
 
-    apply_lambert(col, fragment)
+    // Relieve sólo en tierra: el mar queda plano
+    let n = bump_normal(fragment, uniforms, ROCKY_BUMP, h.max(sea), |q| height(q).max(sea));
+    let mut lit = shade(col, n, fragment.world_position, uniforms, &ROCKY_MATERIAL, water, false);
+
+    if let Some(light) = uniforms.lights.first() {
+        let l = (light.position - fragment.world_position).normalize();
+        // Sombra de la capa de nubes: se busca la nube hacia la luz
+        if let Some(clouds) = &uniforms.clouds {
+            lit = lit * (1.0 - clouds.shadow(fragment.world_position, l));
+        }
+        // Luces nocturnas: aparecen al cruzar el terminador
+        let night = 1.0 - smoothstep(-0.15, 0.1, n.dot(&l));
+        if cities > 0.0 && night > 0.0 {
+            lit = lit + uniforms.pipeline.srgb(1.0, 0.78, 0.42) * (cities * night);
+        }
+    }
+    lit
 }
 
 // Gigante gaseoso: bandas + ruido para perturbar
@@ -123,79 +161,148 @@ pub fn fragment_gas(fragment: &Fragment, uniforms: &Uniforms) -> Color {
     // Layer 1: bandas base más contrastadas
     let bands = (p.y * 7.0 + uniforms.noises[0].get_noise_3d(p.x * 0.7, p.y * 0.7, p.z * 0.7) * 1.2).sin();
     let t = ((bands + 1.0) * 0.5).clamp(0.0, 1.0);
-    let c1 = Color::from_float(0.78, 0.62, 0.48);
-    let c2 = Color::from_float(0.96, 0.88, 0.76);
+    let c1 = uniforms.pipeline.srgb(0.78, 0.62, 0.48);
+    let c2 = uniforms.pipeline.srgb(0.96, 0.88, 0.76);
     let mut col = lerp_color(c1, c2, t);
 
     // Layer 2: acento fino de bandas
     let fine = ((p.y * 24.0 + uniforms.noises[0].get_noise_3d(p.x * 0.5, p.y * 0.5, p.z * 0.5) * 0.6).sin() + 1.0) * 0.5;
-    col = lerp_color(col, Color::from_float(1.0, 0.96, 0.88), fine * 0.18);
+    col = lerp_color(col, uniforms.pipeline.srgb(1.0, 0.96, 0.88), fine * 0.18);
 
     // Layer 3: haze atmosférico
     let d = ((uniforms.noises[1].get_noise_3d(p.x * 1.7, p.y * 1.4, p.z * 1.6) + 1.0) * 0.5).clamp(0.0, 1.0);
-    col = lerp_color(col, Color::from_float(1.0, 1.0, 1.0), d * 0.12);
+    col = lerp_color(col, uniforms.pipeline.srgb(1.0, 1.0, 1.0), d * 0.12);
 
     // Layer 4: tormentas más visibles
     if uniforms.noises.len() > 2 {
         let s = uniforms.noises[2].get_noise_3d(p.x * 0.9 + 1.3, p.y * 0.7 - 0.7, p.z * 0.9);
         let mask = smoothstep(0.5, 0.8, s.abs());
-        col = lerp_color(col, Color::from_float(0.30, 0.27, 0.25), mask * 0.45);
+        col = lerp_color(col, uniforms.pipeline.srgb(0.30, 0.27, 0.25), mask * 0.45);
     }
 
-    apply_lambert(col, fragment)
+    apply_lighting(col, fragment, uniforms, &GAS_MATERIAL, 1.0)
 }
 
-// Luna: gris con variación de cráteres
+// Luna: mares oscuros de ruido y un campo de cráteres (albedo + relieve)
 pub fn fragment_moon(fragment: &Fragment, uniforms: &Uniforms) -> Color {
     let p = fragment.vertex_position;
-    let n1 = ((p.x * 2.0 + p.y * 2.0 + p.z * 2.0).sin() * 0.5 + 0.5).clamp(0.0, 1.0);
-    // si hay ruido disponible, úsalo, sino usa n1
-    let n = if !uniforms.noises.is_empty() {
+    // si hay ruido disponible, úsalo, sino una onda simple
+    let maria = if !uniforms.noises.is_empty() {
         let v = uniforms.noises[0].get_noise_3d(p.x * 1.2, p.y * 1.2, p.z * 1.2);
         ((v + 1.0) * 0.5).clamp(0.0, 1.0)
-    } else { n1 };
-    let base = Color::from_float(0.65, 0.65, 0.67);
-    let dark = Color::from_float(0.25, 0.25, 0.27);
-    let col = lerp_color(dark, base, n);
-    apply_lambert(col, fragment)
+    } else {
+        ((p.x * 2.0 + p.y * 2.0 + p.z * 2.0).sin() * 0.5 + 0.5).clamp(0.0, 1.0)
+    };
+    let base = uniforms.pipeline.srgb(0.65, 0.65, 0.67);
+    let dark = uniforms.pipeline.srgb(0.25, 0.25, 0.27);
+    let mut col = lerp_color(dark, base, maria);
+
+    let Some(craters) = uniforms.craters else {
+        return apply_lighting(col, fragment, uniforms, &MOON_MATERIAL, 0.0);
+    };
+    // fondos más oscuros, bordes y rayos de eyección más claros
+    let (h, albedo) = craters.sample(p);
+    col = if albedo >= 0.0 {
+        lerp_color(col, uniforms.pipeline.srgb(0.92, 0.92, 0.9), albedo)
+    } else {
+        col * (1.0 + albedo)
+    };
+    let n = bump_normal(fragment, uniforms, MOON_BUMP, h, |q| craters.sample(q).0);
+    shade(col, n, fragment.world_position, uniforms, &MOON_MATERIAL, 0.0, false)
 }
 
-// Anillos gaseosos: bandas radiales en el plano XY del modelo
-pub fn fragment_ring(fragment: &Fragment, _uniforms: &Uniforms) -> Color {
-    let x = fragment.vertex_position.x;
-    let y = fragment.vertex_position.y;
-    let r = (x * x + y * y).sqrt();
-    let band = (r * 25.0).sin();
-    let t = ((band + 1.0) * 0.5).clamp(0.0, 1.0);
-    let c1 = Color::from_float(0.75, 0.70, 0.62);
-    let c2 = Color::from_float(0.55, 0.50, 0.42);
-    let base = lerp_color(c1, c2, t * 0.9);
-    // iluminación simple con normal
-    let n = fragment.normal.normalize();
-    let l = Vec3::new(0.0, 0.0, 1.0);
-    let diff = n.dot(&l).max(0.2);
-    base * (0.2 + diff * 0.8)
+// Atmósfera: luz dispersada a lo largo del rayo de vista, se suma a lo que hay detrás
+pub fn fragment_atmosphere(fragment: &Fragment, uniforms: &Uniforms) -> Color {
+    let Some(atmosphere) = &uniforms.atmosphere else {
+        return Color::new(0, 0, 0);
+    };
+    let to_camera = uniforms.camera_position - fragment.world_position;
+    // sólo la cara frontal de la capa, para no sumar dos veces el mismo rayo
+    if fragment.normal.dot(&to_camera) < 0.0 {
+        return Color::new(0, 0, 0);
+    }
+    let c = atmosphere.scatter(uniforms.camera_position, -to_camera.normalize(), uniforms.lights);
+    Color::linear(c.x, c.y, c.z)
 }
 
-fn apply_lambert(base: Color, fragment: &Fragment) -> Color {
-    let light_pos = Vec3::new(0.0, 0.0, 20.0);
-    let l = (light_pos - fragment.vertex_position).normalize();
+// Nubes: capa blanca semitransparente que gira aparte de la superficie
+pub fn fragment_clouds(fragment: &Fragment, uniforms: &Uniforms) -> Color {
+    let transparent = Color::new(0, 0, 0).with_alpha(0.0);
+    let Some(clouds) = &uniforms.clouds else {
+        return transparent;
+    };
+    // sólo la cara frontal de la capa
+    if fragment.normal.dot(&(uniforms.camera_position - fragment.world_position)) < 0.0 {
+        return transparent;
+    }
+    let density = clouds.density(fragment.vertex_position);
+    let col = apply_lighting(uniforms.pipeline.srgb(1.0, 1.0, 1.0), fragment, uniforms, &CLOUD_MATERIAL, 0.0);
+    col.with_alpha(density * clouds.opacity)
+}
+
+// Anillos gaseosos: color y opacidad del perfil radial según la UV radial,
+// semitransparentes (huecos de Cassini y Encke casi vacíos)
+pub fn fragment_ring(fragment: &Fragment, uniforms: &Uniforms) -> Color {
+    let transparent = Color::new(0, 0, 0).with_alpha(0.0);
+    let Some(profile) = uniforms.ring else {
+        return transparent;
+    };
+    let u = fragment.tex_coords.x;
+    let density = profile.density(u);
+    if density <= 0.0 {
+        return transparent;
+    }
+    let base = match profile {
+        RingProfile::Strip(strip) => uniforms.pipeline.decode(strip.sample(u)),
+        RingProfile::Procedural => {
+            let band = (u * 20.0).sin();
+            let t = ((band + 1.0) * 0.5).clamp(0.0, 1.0);
+            let c1 = uniforms.pipeline.srgb(0.75, 0.70, 0.62);
+            let c2 = uniforms.pipeline.srgb(0.55, 0.50, 0.42);
+            lerp_color(c1, c2, t * 0.9)
+        }
+    };
+    // el anillo es un plano: se ilumina por ambas caras
+    shade(base, fragment.normal.normalize(), fragment.world_position, uniforms, &RING_MATERIAL, 0.0, true).with_alpha(density)
+}
+
+fn apply_lighting(base: Color, fragment: &Fragment, uniforms: &Uniforms, material: &Material, spec_mask: f32) -> Color {
+    shade(base, fragment.normal.normalize(), fragment.world_position, uniforms, material, spec_mask, false)
+}
+
+// Normal de mundo inclinada según el gradiente de `height` (espacio de objeto),
+// estimado con diferencias finitas hacia adelante desde `h0` = height(p).
+fn bump_normal(fragment: &Fragment, uniforms: &Uniforms, strength: f32, h0: f32, height: impl Fn(Vec3) -> f32) -> Vec3 {
     let n = fragment.normal.normalize();
-    let diff = n.dot(&l).max(0.0);
-    let ambient = 0.2;
-    base * (ambient + diff * 0.8)
+    if strength <= 0.0 {
+        return n;
+    }
+    let p = fragment.vertex_position;
+    let e = 1e-3;
+    let grad = Vec3::new(
+        height(p + Vec3::new(e, 0.0, 0.0)) - h0,
+        height(p + Vec3::new(0.0, e, 0.0)) - h0,
+        height(p + Vec3::new(0.0, 0.0, e)) - h0,
+    ) / e;
+    // model = escala uniforme * rotación: se rota el gradiente sin escalarlo
+    let model3 = mat4_to_mat3(&uniforms.model_matrix);
+    let scale = model3.column(0).norm().max(1e-6);
+    let g = model3 * grad / scale;
+    let g = g - n * n.dot(&g);
+    (n - g * strength).normalize()
 }
 
 fn lerp_color(a: Color, b: Color, t: f32) -> Color {
     let t = t.clamp(0.0, 1.0);
     Color {
-        r: (a.r as f32 + (b.r as f32 - a.r as f32) * t).round() as u8,
-        g: (a.g as f32 + (b.g as f32 - a.g as f32) * t).round() as u8,
-        b: (a.b as f32 + (b.b as f32 - a.b as f32) * t).round() as u8,
+        r: a.r + (b.r - a.r) * t,
+        g: a.g + (b.g - a.g) * t,
+        b: a.b + (b.b - a.b) * t,
+        a: a.a + (b.a - a.a) * t,
     }
 }
 
-fn smoothstep(edge0: f32, edge1: f32, x: f32) -> f32 {
+pub fn smoothstep(edge0: f32, edge1: f32, x: f32) -> f32 {
     let t = ((x - edge0) / (edge1 - edge0)).clamp(0.0, 1.0);
     t * t * (3.0 - 2.0 * t)
 }
@@ -0,0 +1,66 @@
+use nalgebra_glm::Vec3;
+use crate::{light::Light, ring::RingProfile, shaders::smoothstep};
+
+// Geometría simplificada que proyecta sombra (esferas envolventes y anillos planos)
+#[derive(Clone, Copy)]
+pub enum Occluder<'a> {
+    Sphere { center: Vec3, radius: f32 },
+    Ring { center: Vec3, normal: Vec3, inner: f32, outer: f32, opacity: f32, profile: &'a RingProfile },
+}
+
+// Fracción (0..1) del disco de la luz visible desde `p`. La penumbra sale del
+// radio aparente de la luz, así los eclipses y las sombras del anillo son suaves.
+pub fn light_visibility(p: Vec3, light: &Light, occluders: &[Occluder]) -> f32 {
+    let to_light = light.position - p;
+    let light_dist = to_light.norm().max(1e-4);
+    let l = to_light / light_dist;
+    let light_ang = (light.radius / light_dist).min(1.0).asin();
+
+    let mut visibility = 1.0;
+    for occ in occluders {
+        match *occ {
+            Occluder::Sphere { center, radius } => {
+                let to_occ = center - p;
+                let d_occ = to_occ.norm();
+                // el propio cuerpo no se sombrea a sí mismo (eso ya lo hace n·l)
+                if d_occ <= radius * 1.01 {
+                    continue;
+                }
+                let t = to_occ.dot(&l);
+                if t <= 0.0 || t > light_dist {
+                    continue;
+                }
+                let occ_ang = (radius / d_occ).min(1.0).asin();
+                let sep = (to_occ / d_occ).dot(&l).clamp(-1.0, 1.0).acos();
+                let vis = if light_ang < 1e-4 {
+                    if sep < occ_ang { 0.0 } else { 1.0 }
+                } else {
+                    // cobertura máxima cuando un disco queda dentro del otro
+                    let covered = (occ_ang / light_ang).powi(2).min(1.0);
+                    let overlap = 1.0 - smoothstep((occ_ang - light_ang).abs(), occ_ang + light_ang, sep);
+                    1.0 - covered * overlap
+                };
+                visibility *= vis;
+            }
+            Occluder::Ring { center, normal, inner, outer, opacity, profile } => {
+                let denom = normal.dot(&l);
+                if denom.abs() < 1e-5 {
+                    continue;
+                }
+                let t = normal.dot(&(center - p)) / denom;
+                if t <= 1e-3 || t > light_dist {
+                    continue;
+                }
+                let r = (p + l * t - center).norm();
+                let pen = (t * light.radius / light_dist).max(1e-3);
+                let inside = smoothstep(inner - pen, inner + pen, r) * (1.0 - smoothstep(outer - pen, outer + pen, r));
+                let density = profile.density(((r - inner) / (outer - inner)).clamp(0.0, 1.0));
+                visibility *= 1.0 - opacity * inside * density;
+            }
+        }
+        if visibility <= 0.0 {
+            return 0.0;
+        }
+    }
+    visibility
+}
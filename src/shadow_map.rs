@@ -0,0 +1,161 @@
+use nalgebra_glm::{look_at, perspective, Mat4, Vec3, Vec4};
+use crate::{framebuffer::SINGLE_SAMPLE, ring::RingProfile, triangle::triangle, vertex::Vertex};
+
+// Mapa de sombras de una luz: profundidad vista desde la luz, con un frustum
+// ajustado a cada caster. Con un único frustum para todos, la estrella queda
+// dentro de él en cuanto dos planetas están a lados opuestos. Cubre cualquier
+// malla (anillos, naves), no sólo esferas.
+pub struct ShadowMap {
+    size: usize,
+    pub light_index: usize,
+    // sesgo mínimo y sesgo extra en superficies rasantes (evita "acne")
+    pub bias: f32,
+    pub slope_bias: f32,
+    // radio del filtro PCF en texels (0 = sin filtrar, 1 = 3x3, ...)
+    pub pcf_radius: i32,
+    light_pos: Vec3,
+    viewport: Mat4,
+    // los buffers se reutilizan entre cuadros; sólo cuentan los `active` primeros
+    views: Vec<ShadowView>,
+    active: usize,
+}
+
+// Vista desde la luz hacia un caster, con su propio buffer de profundidad
+struct ShadowView {
+    view_projection: Mat4,
+    near: f32,
+    depth: Vec<f32>,
+}
+
+impl ShadowMap {
+    pub fn new(size: usize) -> Self {
+        let s = size as f32;
+        Self {
+            size,
+            light_index: 0,
+            bias: 0.0015,
+            slope_bias: 0.002,
+            pcf_radius: 1,
+            light_pos: Vec3::zeros(),
+            viewport: Mat4::new(
+                s / 2.0, 0.0, 0.0, s / 2.0,
+                0.0, -s / 2.0, 0.0, s / 2.0,
+                0.0, 0.0, 1.0, 0.0,
+                0.0, 0.0, 0.0, 1.0,
+            ),
+            views: Vec::new(),
+            active: 0,
+        }
+    }
+
+    // Prepara una pasada desde la luz en `light_pos`; después cada caster se
+    // añade con `render_caster`
+    pub fn begin(&mut self, light_pos: Vec3) {
+        self.light_pos = light_pos;
+        self.active = 0;
+    }
+
+    // Rasteriza sólo profundidad de una malla con su model matrix, en una vista
+    // que apunta a su esfera envolvente (center, radius). `coverage` (perfil por
+    // UV radial) recorta las zonas casi transparentes, como los huecos del anillo.
+    pub fn render_caster(&mut self, center: Vec3, radius: f32, vertices: &[Vertex], model: &Mat4, coverage: Option<&RingProfile>) {
+        let to_center = center - self.light_pos;
+        let dist = to_center.norm();
+        // con la luz dentro del caster ningún frustum lo contiene: no proyecta sombra
+        if dist <= radius * 1.01 {
+            return;
+        }
+        let up = if (to_center / dist).y.abs() > 0.99 { Vec3::new(1.0, 0.0, 0.0) } else { Vec3::new(0.0, 1.0, 0.0) };
+        let fov = 2.0 * (radius / dist).asin();
+        // algo de holgura para que ningún vértice del caster quede antes del plano cercano
+        let near = ((dist - radius) * 0.9).max(0.05);
+        let far = dist + radius;
+        // nalgebra_glm::perspective recibe (aspect, fovy, near, far)
+        let projection = perspective(1.0, fov, near, far);
+        let view_projection = projection * look_at(&self.light_pos, &center, &up);
+
+        if self.active == self.views.len() {
+            self.views.push(ShadowView { view_projection, near, depth: vec![f32::INFINITY; self.size * self.size] });
+        } else {
+            let view = &mut self.views[self.active];
+            view.view_projection = view_projection;
+            view.near = near;
+            view.depth.fill(f32::INFINITY);
+        }
+        let view = &mut self.views[self.active];
+        self.active += 1;
+
+        let m = view.view_projection * model;
+        let clipped: Vec<(Vertex, f32)> = vertices
+            .iter()
+            .map(|v| {
+                let clip = m * Vec4::new(v.position.x, v.position.y, v.position.z, 1.0);
+                let ndc = Vec4::new(clip.x / clip.w, clip.y / clip.w, clip.z / clip.w, 1.0);
+                let screen = self.viewport * ndc;
+                let mut out = v.clone();
+                out.transformed_position = Vec3::new(screen.x, screen.y, screen.z);
+                (out, clip.w)
+            })
+            .collect();
+
+        for tri in clipped.chunks_exact(3) {
+            // detrás del plano cercano la división por w no tiene sentido
+            if tri.iter().any(|(_, w)| *w <= view.near) {
+                continue;
+            }
+            for frag in triangle(&tri[0].0, &tri[1].0, &tri[2].0, &SINGLE_SAMPLE, self.size, self.size) {
+                if coverage.is_some_and(|c| c.density(frag.tex_coords.x) < 0.5) {
+                    continue;
+                }
+                let idx = frag.position.y as usize * self.size + frag.position.x as usize;
+                if frag.depth < view.depth[idx] {
+                    view.depth[idx] = frag.depth;
+                }
+            }
+        }
+    }
+
+    // Fracción iluminada (0..1) de un punto de mundo con PCF; `ndl` escala el sesgo.
+    // Basta con que una vista lo tape.
+    pub fn visibility(&self, p: Vec3, ndl: f32) -> f32 {
+        self.views[..self.active]
+            .iter()
+            .map(|view| self.view_visibility(view, p, ndl))
+            .fold(1.0, f32::min)
+    }
+
+    fn view_visibility(&self, view: &ShadowView, p: Vec3, ndl: f32) -> f32 {
+        let clip = view.view_projection * Vec4::new(p.x, p.y, p.z, 1.0);
+        if clip.w <= 0.0 {
+            return 1.0;
+        }
+        let ndc = Vec4::new(clip.x / clip.w, clip.y / clip.w, clip.z / clip.w, 1.0);
+        // fuera del cono o antes del caster no hay nada que lo tape; más allá del
+        // plano lejano queda detrás del caster, así que se compara como si estuviera en él
+        if ndc.x.abs() > 1.0 || ndc.y.abs() > 1.0 || ndc.z < -1.0 {
+            return 1.0;
+        }
+        let s = self.viewport * Vec4::new(ndc.x, ndc.y, ndc.z.min(1.0), 1.0);
+        // el sesgo crece con la pendiente respecto a la luz y con el alcance del PCF
+        let ndl = ndl.clamp(1e-3, 1.0);
+        let tan_theta = ((1.0 - ndl * ndl).sqrt() / ndl).min(10.0);
+        let bias = self.bias + self.slope_bias * tan_theta * (1 + self.pcf_radius) as f32;
+        let depth = s.z - bias;
+
+        let n = self.size as i32;
+        let (cx, cy) = (s.x as i32, s.y as i32);
+        let mut lit = 0;
+        let mut total = 0;
+        for dy in -self.pcf_radius..=self.pcf_radius {
+            for dx in -self.pcf_radius..=self.pcf_radius {
+                let x = (cx + dx).clamp(0, n - 1);
+                let y = (cy + dy).clamp(0, n - 1);
+                total += 1;
+                if depth <= view.depth[(y * n + x) as usize] {
+                    lit += 1;
+                }
+            }
+        }
+        lit as f32 / total as f32
+    }
+}
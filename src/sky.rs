@@ -0,0 +1,265 @@
+use fastnoise_lite::{FastNoiseLite, FractalType, NoiseType};
+use nalgebra_glm::{Mat4, Vec3, Vec4};
+use std::path::Path;
+use crate::{color::{srgb_to_linear, Color, ColorPipeline}, framebuffer::Framebuffer, rng::Rng, shaders::smoothstep, texture::ImageTexture};
+
+// Resolución del mapa precalculado de la banda galáctica (equirectangular)
+const BAND_WIDTH: usize = 1024;
+const BAND_HEIGHT: usize = 512;
+// Magnitudes visibles: la más brillante y la más débil que se generan
+const BRIGHTEST_MAGNITUDE: f32 = -1.5;
+const FAINTEST_MAGNITUDE: f32 = 6.5;
+// Brillo (lineal) de una estrella de la magnitud más débil y tope para las brillantes
+const FAINTEST_BRIGHTNESS: f32 = 0.035;
+const MAX_BRIGHTNESS: f32 = 6.0;
+
+// Dirección de vista de cada píxel, sin la traslación de la cámara: el cielo está
+// en el infinito y sólo gira con ella
+pub struct ViewRays {
+    inverse: Mat4,
+    width: f32,
+    height: f32,
+}
+
+impl ViewRays {
+    pub fn new(view: &Mat4, projection: &Mat4, width: usize, height: usize) -> Self {
+        let inverse = (projection * rotation_only(view)).try_inverse().unwrap_or(Mat4::identity());
+        Self { inverse, width: width as f32, height: height as f32 }
+    }
+
+    // Dirección de mundo (normalizada) que pasa por el centro del píxel (x, y)
+    pub fn direction(&self, x: usize, y: usize) -> Vec3 {
+        let ndc_x = (x as f32 + 0.5) / self.width * 2.0 - 1.0;
+        let ndc_y = 1.0 - (y as f32 + 0.5) / self.height * 2.0;
+        let far = self.inverse * Vec4::new(ndc_x, ndc_y, 1.0, 1.0);
+        Vec3::new(far.x, far.y, far.z).normalize()
+    }
+}
+
+fn rotation_only(view: &Mat4) -> Mat4 {
+    let mut m = *view;
+    m[(0, 3)] = 0.0;
+    m[(1, 3)] = 0.0;
+    m[(2, 3)] = 0.0;
+    m
+}
+
+struct Star {
+    direction: Vec3,
+    color: Color, // radiancia lineal, ya multiplicada por el brillo
+}
+
+// Cielo procedural fijo en el espacio de mundo: estrellas con semilla (magnitud y
+// color según temperatura) y una banda galáctica hecha con ruido, precalculada en
+// un mapa equirectangular para no evaluar el ruido por píxel en cada cuadro.
+pub struct Starfield {
+    stars: Vec<Star>,
+    band: Vec<Color>,
+}
+
+impl Starfield {
+    // `count` estrellas; hay más cerca del plano de la galaxia
+    pub fn generate(seed: u32, count: usize) -> Self {
+        let mut rng = Rng::new(seed);
+        let noise = band_noise(seed as i32);
+        let band: Vec<Color> = (0..BAND_WIDTH * BAND_HEIGHT)
+            .map(|i| band_color(&noise, equirect_direction(i % BAND_WIDTH, i / BAND_WIDTH)))
+            .collect();
+
+        let mut stars = Vec::with_capacity(count);
+        while stars.len() < count {
+            let z = rng.next() * 2.0 - 1.0;
+            let phi = rng.next() * std::f32::consts::TAU;
+            let s = (1.0 - z * z).sqrt();
+            let direction = Vec3::new(s * phi.cos(), s * phi.sin(), z);
+            // densidad: la mitad uniforme, la otra mitad concentrada en la banda
+            if rng.next() > 0.5 + 0.5 * band_profile(direction) {
+                continue;
+            }
+
+            // N(< m) ~ 10^(0.4 m): muchas débiles y pocas brillantes
+            let m = (FAINTEST_MAGNITUDE + rng.next().max(1e-6).log10() / 0.4).max(BRIGHTEST_MAGNITUDE);
+            let brightness = (FAINTEST_BRIGHTNESS * 10f32.powf(-0.4 * (m - FAINTEST_MAGNITUDE))).min(MAX_BRIGHTNESS);
+            // las frías (rojizas) son las más comunes
+            let temperature = 2800.0 + 27_000.0 * rng.next().powf(2.5);
+            let (r, g, b) = blackbody(temperature);
+            stars.push(Star { direction, color: Color::linear(srgb_to_linear(r), srgb_to_linear(g), srgb_to_linear(b)) * brightness });
+        }
+        Self { stars, band }
+    }
+
+    // Suma el cielo al fondo del framebuffer, antes de dibujar los cuerpos. Estrellas y
+    // banda son radiancia calculada: como la atmósfera, no dependen del pipeline.
+    pub fn draw(&self, framebuffer: &mut Framebuffer, view: &Mat4, projection: &Mat4, viewport: &Mat4, galactic_band: bool) {
+        if galactic_band {
+            let rays = ViewRays::new(view, projection, framebuffer.width, framebuffer.height);
+            for y in 0..framebuffer.height {
+                for x in 0..framebuffer.width {
+                    let c = self.sample_band(rays.direction(x, y));
+                    framebuffer.add_background(x as i32, y as i32, c);
+                }
+            }
+        }
+
+        // estrellas como puntos en el infinito (w = 0: no les afecta la traslación)
+        let m = projection * view;
+        for star in &self.stars {
+            let d = star.direction;
+            let clip = m * Vec4::new(d.x, d.y, d.z, 0.0);
+            if clip.w <= 0.0 {
+                continue;
+            }
+            let ndc = Vec4::new(clip.x / clip.w, clip.y / clip.w, 0.0, 1.0);
+            if ndc.x.abs() > 1.01 || ndc.y.abs() > 1.01 {
+                continue;
+            }
+            let screen = viewport * ndc;
+            splat(framebuffer, screen.x, screen.y, star.color, framebuffer.supersample);
+        }
+    }
+
+    fn sample_band(&self, d: Vec3) -> Color {
+        let u = (d.z.atan2(d.x) / std::f32::consts::TAU + 0.5) * BAND_WIDTH as f32;
+        let v = (d.y.clamp(-1.0, 1.0).acos() / std::f32::consts::PI) * BAND_HEIGHT as f32;
+        let x = (u as usize).min(BAND_WIDTH - 1);
+        let y = (v as usize).min(BAND_HEIGHT - 1);
+        self.band[y * BAND_WIDTH + x]
+    }
+}
+
+// Estrella como una gaussiana pequeña repartida entre los píxeles vecinos, para que
+// no parpadee al moverse menos de un píxel. Con supersampling (`scale` > 1) la mancha
+// se agranda `scale` veces y suma `scale`² veces más: al reducir la captura queda
+// igual que en la ventana, en vez de una estrella más débil en un solo píxel.
+fn splat(framebuffer: &mut Framebuffer, x: f32, y: f32, color: Color, scale: usize) {
+    let (cx, cy) = (x - 0.5, y - 0.5);
+    let (ox, oy) = (cx.round() as i32, cy.round() as i32);
+    let r = scale.max(1) as i32;
+    let sigma = 0.45 * r as f32;
+    let weight = |i: i32, j: i32| {
+        let (dx, dy) = ((ox + i) as f32 - cx, (oy + j) as f32 - cy);
+        (-(dx * dx + dy * dy) / (2.0 * sigma * sigma)).exp()
+    };
+    let total: f32 = (-r..=r).flat_map(|j| (-r..=r).map(move |i| weight(i, j))).sum();
+    let energy = (r * r) as f32 / total;
+    for j in -r..=r {
+        for i in -r..=r {
+            framebuffer.add_background(ox + i, oy + j, color * (weight(i, j) * energy));
+        }
+    }
+}
+
+// Normal del plano galáctico: inclinado para que la banda cruce la vista en diagonal
+fn galactic_normal() -> Vec3 {
+    Vec3::new(0.55, 0.75, -0.37).normalize()
+}
+
+// 1 en el plano galáctico, cae a 0 lejos de él
+fn band_profile(d: Vec3) -> f32 {
+    let latitude = d.dot(&galactic_normal());
+    (-(latitude / 0.22).powi(2)).exp()
+}
+
+fn band_noise(seed: i32) -> FastNoiseLite {
+    let mut n = FastNoiseLite::with_seed(seed);
+    n.set_noise_type(Some(NoiseType::Perlin));
+    n.set_fractal_type(Some(FractalType::FBm));
+    n.set_fractal_octaves(Some(5));
+    n.set_frequency(Some(2.5));
+    n
+}
+
+// Brillo de la banda: nubes de ruido a lo largo del plano, núcleo más cálido y una
+// franja de polvo oscuro en el centro
+fn band_color(noise: &FastNoiseLite, d: Vec3) -> Color {
+    let latitude = d.dot(&galactic_normal());
+    let profile = band_profile(d);
+    if profile < 1e-3 {
+        return Color::linear(0.0, 0.0, 0.0);
+    }
+    let clouds = (noise.get_noise_3d(d.x, d.y, d.z) + 1.0) * 0.5;
+    let detail = (noise.get_noise_3d(d.x * 4.0 + 7.0, d.y * 4.0, d.z * 4.0) + 1.0) * 0.5;
+    let dust = (-(latitude / 0.04).powi(2)).exp() * smoothstep(0.35, 0.7, detail);
+    let glow = profile * smoothstep(0.25, 0.8, clouds) * (1.0 - 0.8 * dust);
+    let core = (-(latitude / 0.1).powi(2)).exp();
+    let warm = Color::linear(0.050, 0.042, 0.032);
+    let cool = Color::linear(0.030, 0.034, 0.045);
+    (warm * core + cool * (1.0 - core)) * glow
+}
+
+// Dirección de la celda (x, y) del mapa equirectangular (y = 0 arriba, +Y)
+fn equirect_direction(x: usize, y: usize) -> Vec3 {
+    let phi = ((x as f32 + 0.5) / BAND_WIDTH as f32 - 0.5) * std::f32::consts::TAU;
+    let theta = (y as f32 + 0.5) / BAND_HEIGHT as f32 * std::f32::consts::PI;
+    Vec3::new(theta.sin() * phi.cos(), theta.cos(), theta.sin() * phi.sin())
+}
+
+// Color aproximado de un cuerpo negro (ajuste de Tanner Helland), normalizado a su
+// canal mayor
+fn blackbody(kelvin: f32) -> (f32, f32, f32) {
+    let t = kelvin / 100.0;
+    let r = if t <= 66.0 { 255.0 } else { 329.7 * (t - 60.0).powf(-0.1332) };
+    let g = if t <= 66.0 { 99.47 * t.ln() - 161.12 } else { 288.12 * (t - 60.0).powf(-0.0755) };
+    let b = if t >= 66.0 { 255.0 } else if t <= 19.0 { 0.0 } else { 138.52 * (t - 10.0).ln() - 305.04 };
+    let (r, g, b) = (r.clamp(0.0, 255.0), g.clamp(0.0, 255.0), b.clamp(0.0, 255.0));
+    let m = r.max(g).max(b);
+    (r / m, g / m, b / m)
+}
+
+// Nombres de las caras de un cubemap dentro de su carpeta, en orden +X, -X, +Y, -Y, +Z, -Z
+const CUBE_FACES: [&str; 6] = ["px.png", "nx.png", "py.png", "ny.png", "pz.png", "nz.png"];
+
+// Fondo a partir de imágenes: un cubemap (seis caras) o un panorama equirectangular,
+// muestreado por la dirección de vista de cada píxel
+pub enum SkyMap {
+    Cube(Box<[ImageTexture; 6]>),
+    Equirect(ImageTexture),
+}
+
+impl SkyMap {
+    // Una carpeta se lee como cubemap (px.png, nx.png, ...); un archivo, como panorama
+    pub fn load(path: &Path) -> Result<Self, image::ImageError> {
+        if path.is_dir() {
+            let [px, nx, py, ny, pz, nz] = CUBE_FACES.map(|face| ImageTexture::load(&path.join(face)));
+            Ok(SkyMap::Cube(Box::new([px?, nx?, py?, ny?, pz?, nz?])))
+        } else {
+            Ok(SkyMap::Equirect(ImageTexture::load(path)?))
+        }
+    }
+
+    pub fn sample(&self, d: Vec3) -> Color {
+        match self {
+            SkyMap::Equirect(texture) => {
+                let u = d.z.atan2(d.x) / std::f32::consts::TAU + 0.5;
+                let v = d.y.clamp(-1.0, 1.0).acos() / std::f32::consts::PI;
+                texture.sample(u, v, true)
+            }
+            SkyMap::Cube(faces) => {
+                // convención de cubemaps de OpenGL: eje dominante y coordenadas de la cara
+                let (ax, ay, az) = (d.x.abs(), d.y.abs(), d.z.abs());
+                let (face, sc, tc, ma) = if ax >= ay && ax >= az {
+                    if d.x > 0.0 { (0, -d.z, -d.y, ax) } else { (1, d.z, -d.y, ax) }
+                } else if ay >= az {
+                    if d.y > 0.0 { (2, d.x, d.z, ay) } else { (3, d.x, -d.z, ay) }
+                } else if d.z > 0.0 {
+                    (4, d.x, -d.y, az)
+                } else {
+                    (5, -d.x, -d.y, az)
+                };
+                faces[face].sample((sc / ma + 1.0) * 0.5, (tc / ma + 1.0) * 0.5, false)
+            }
+        }
+    }
+
+    // Pinta el fondo completo (antes de los cuerpos); `intensity` escala la imagen
+    // una vez decodificada al espacio de trabajo
+    pub fn draw(&self, framebuffer: &mut Framebuffer, view: &Mat4, projection: &Mat4, intensity: f32, pipeline: ColorPipeline) {
+        let rays = ViewRays::new(view, projection, framebuffer.width, framebuffer.height);
+        for y in 0..framebuffer.height {
+            for x in 0..framebuffer.width {
+                let c = pipeline.decode(self.sample(rays.direction(x, y))) * intensity;
+                framebuffer.add_background(x as i32, y as i32, c);
+            }
+        }
+    }
+}
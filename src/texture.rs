@@ -0,0 +1,70 @@
+use crate::color::Color;
+
+// Textura 1D (tira) con color y opacidad, muestreada con filtro lineal.
+// Sirve para perfiles radiales como los de los anillos de Saturno. Devuelve el color
+// sRGB tal cual: quien lo usa lo decodifica con su `ColorPipeline`.
+pub struct StripTexture {
+    texels: Vec<[u8; 4]>,
+}
+
+impl StripTexture {
+    // Carga un PNG y usa su fila central (las tiras suelen ser N x 1 o pocos px de alto)
+    pub fn load(path: &str) -> Result<Self, image::ImageError> {
+        let img = image::open(path)?.to_rgba8();
+        let y = img.height() / 2;
+        let texels = (0..img.width())
+            .map(|x| img.get_pixel(x, y).0)
+            .collect();
+        Ok(Self { texels })
+    }
+
+    // `u` en 0..1 (se limita a los bordes)
+    pub fn sample(&self, u: f32) -> Color {
+        let last = self.texels.len().saturating_sub(1);
+        let x = u.clamp(0.0, 1.0) * last as f32;
+        let i = (x.floor() as usize).min(last);
+        let j = (i + 1).min(last);
+        let t = x - i as f32;
+        let texel = |[r, g, b, a]: [u8; 4]| Color::new(r, g, b).with_alpha(a as f32 / 255.0);
+        let (a, b) = (texel(self.texels[i]), texel(self.texels[j]));
+        let mix = |p: f32, q: f32| p + (q - p) * t;
+        Color { r: mix(a.r, b.r), g: mix(a.g, b.g), b: mix(a.b, b.b), a: mix(a.a, b.a) }
+    }
+}
+
+// Textura 2D RGB con filtro bilineal (fondos de cielo). Igual que las tiras, devuelve
+// sRGB sin decodificar.
+pub struct ImageTexture {
+    pub width: usize,
+    pub height: usize,
+    texels: Vec<[u8; 3]>,
+}
+
+impl ImageTexture {
+    pub fn load(path: &std::path::Path) -> Result<Self, image::ImageError> {
+        let img = image::open(path)?.to_rgb8();
+        let (width, height) = (img.width() as usize, img.height() as usize);
+        Ok(Self { width, height, texels: img.pixels().map(|p| p.0).collect() })
+    }
+
+    // `u`, `v` en 0..1 desde la esquina superior izquierda; `wrap_u` repite en
+    // horizontal (panoramas) y si no se limita al borde (caras de un cubo)
+    pub fn sample(&self, u: f32, v: f32, wrap_u: bool) -> Color {
+        let x = u * self.width as f32 - 0.5;
+        let y = (v * self.height as f32 - 0.5).clamp(0.0, self.height as f32 - 1.0);
+        let (x0, y0) = (x.floor(), y.floor());
+        let (tx, ty) = (x - x0, y - y0);
+        let column = |i: i32| {
+            if wrap_u { i.rem_euclid(self.width as i32) as usize } else { i.clamp(0, self.width as i32 - 1) as usize }
+        };
+        let (c0, c1) = (column(x0 as i32), column(x0 as i32 + 1));
+        let (r0, r1) = (y0 as usize, (y0 as usize + 1).min(self.height - 1));
+        let at = |c: usize, r: usize| {
+            let [red, green, blue] = self.texels[r * self.width + c];
+            Color::new(red, green, blue)
+        };
+        let top = at(c0, r0) * (1.0 - tx) + at(c1, r0) * tx;
+        let bottom = at(c0, r1) * (1.0 - tx) + at(c1, r1) * tx;
+        top * (1.0 - ty) + bottom * ty
+    }
+}
@@ -0,0 +1,52 @@
+use crate::color::Color;
+
+// Curva que comprime el color HDR a [0, 1] antes de mostrarlo o guardarlo
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ToneMapper {
+    // recorte directo, como antes del framebuffer HDR
+    Clamp,
+    Reinhard,
+    // curva fílmica ACES (ajuste de Narkowicz)
+    Aces,
+}
+
+impl ToneMapper {
+    pub fn next(self) -> Self {
+        match self {
+            ToneMapper::Clamp => ToneMapper::Reinhard,
+            ToneMapper::Reinhard => ToneMapper::Aces,
+            ToneMapper::Aces => ToneMapper::Clamp,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ToneMapper::Clamp => "recorte",
+            ToneMapper::Reinhard => "Reinhard",
+            ToneMapper::Aces => "ACES",
+        }
+    }
+
+    fn map(self, x: f32) -> f32 {
+        match self {
+            ToneMapper::Clamp => x,
+            ToneMapper::Reinhard => x / (1.0 + x),
+            ToneMapper::Aces => (x * (2.51 * x + 0.03)) / (x * (2.43 * x + 0.59) + 0.14),
+        }
+        .clamp(0.0, 1.0)
+    }
+}
+
+// Resolve del HDR: exposición (multiplicador lineal) y curva por canal
+#[derive(Debug, Clone, Copy)]
+pub struct ToneMapping {
+    pub operator: ToneMapper,
+    pub exposure: f32,
+}
+
+impl ToneMapping {
+    pub fn apply(&self, c: Color) -> Color {
+        let map = |x: f32| self.operator.map(x * self.exposure);
+        Color { r: map(c.r), g: map(c.g), b: map(c.b), a: c.a }
+    }
+}
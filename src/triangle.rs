@@ -1,38 +1,63 @@
-use crate::{color::Color, fragment::Fragment, vertex::Vertex};
-use nalgebra_glm::{dot, Vec2, Vec3};
+use crate::{fragment::Fragment, framebuffer::MAX_SAMPLES, vertex::Vertex};
+use nalgebra_glm::{Vec2, Vec3};
 
 fn edge(a: &Vec3, b: &Vec3, c: &Vec3) -> f32 {
     (c.x - a.x) * (b.y - a.y) - (c.y - a.y) * (b.x - a.x)
 }
 
-pub fn triangle(v1: &Vertex, v2: &Vertex, v3: &Vertex) -> Vec<Fragment> {
+// `samples` son las posiciones de muestra dentro del píxel (0..1). Con una sola
+// muestra en el centro es la rasterización de siempre; con varias (MSAA) se genera
+// un fragmento por píxel con alguna muestra cubierta, su máscara y la profundidad
+// de cada muestra. Los atributos se interpolan en el centro si está cubierto, o en
+// la primera muestra cubierta para no extrapolar fuera del triángulo. Sólo se
+// recorren los píxeles dentro de `width` x `height`.
+pub fn triangle(v1: &Vertex, v2: &Vertex, v3: &Vertex, samples: &[(f32, f32)], width: usize, height: usize) -> Vec<Fragment> {
     let mut fragments = Vec::new();
     let a = v1.transformed_position;
     let b = v2.transformed_position;
     let c = v3.transformed_position;
 
-    let min_x = a.x.min(b.x).min(c.x).floor() as i32;
-    let min_y = a.y.min(b.y).min(c.y).floor() as i32;
-    let max_x = a.x.max(b.x).max(c.x).ceil() as i32;
-    let max_y = a.y.max(b.y).max(c.y).ceil() as i32;
+    // caja envolvente recortada a la pantalla: un vértice proyectado muy lejos
+    // no debe hacer recorrer millones de píxeles invisibles
+    let min_x = (a.x.min(b.x).min(c.x).floor() as i32).max(0);
+    let min_y = (a.y.min(b.y).min(c.y).floor() as i32).max(0);
+    let max_x = (a.x.max(b.x).max(c.x).ceil() as i32).min(width as i32 - 1);
+    let max_y = (a.y.max(b.y).max(c.y).ceil() as i32).min(height as i32 - 1);
 
-    let light_dir = Vec3::new(0.0, 0.0, 1.0);
     let area = edge(&a, &b, &c);
     if area.abs() < 1e-6 { return fragments; }
 
+    let weights = |p: &Vec3| (edge(&b, &c, p) / area, edge(&c, &a, p) / area, edge(&a, &b, p) / area);
+    let inside = |(w1, w2, w3): (f32, f32, f32)| w1 >= 0.0 && w2 >= 0.0 && w3 >= 0.0;
+
     for y in min_y..=max_y {
         for x in min_x..=max_x {
-            let p = Vec3::new(x as f32 + 0.5, y as f32 + 0.5, 0.0);
-            let w1 = edge(&b, &c, &p) / area;
-            let w2 = edge(&c, &a, &p) / area;
-            let w3 = edge(&a, &b, &p) / area;
-            if w1 >= 0.0 && w2 >= 0.0 && w3 >= 0.0 {
-                let normal = (v1.transformed_normal * w1 + v2.transformed_normal * w2 + v3.transformed_normal * w3).normalize();
-                let intensity = dot(&normal, &light_dir).max(0.0);
-                let depth = a.z * w1 + b.z * w2 + c.z * w3;
-                let vertex_position = v1.position * w1 + v2.position * w2 + v3.position * w3;
-                fragments.push(Fragment::new(Vec2::new(x as f32, y as f32), depth, normal, intensity, vertex_position));
+            let mut coverage = 0u32;
+            let mut sample_depths = [f32::INFINITY; MAX_SAMPLES];
+            let mut first_covered = None;
+            for (i, (sx, sy)) in samples.iter().take(MAX_SAMPLES).enumerate() {
+                let p = Vec3::new(x as f32 + sx, y as f32 + sy, 0.0);
+                let w = weights(&p);
+                if inside(w) {
+                    coverage |= 1 << i;
+                    sample_depths[i] = a.z * w.0 + b.z * w.1 + c.z * w.2;
+                    first_covered.get_or_insert(p);
+                }
             }
+            let Some(first_covered) = first_covered else { continue };
+
+            let center = Vec3::new(x as f32 + 0.5, y as f32 + 0.5, 0.0);
+            let (w1, w2, w3) = if inside(weights(&center)) { weights(&center) } else { weights(&first_covered) };
+            let normal = (v1.transformed_normal * w1 + v2.transformed_normal * w2 + v3.transformed_normal * w3).normalize();
+            let depth = a.z * w1 + b.z * w2 + c.z * w3;
+            let vertex_position = v1.position * w1 + v2.position * w2 + v3.position * w3;
+            let world_position = v1.world_position * w1 + v2.world_position * w2 + v3.world_position * w3;
+            let tex_coords = v1.tex_coords * w1 + v2.tex_coords * w2 + v3.tex_coords * w3;
+            fragments.push(Fragment {
+                coverage,
+                sample_depths,
+                ..Fragment::new(Vec2::new(x as f32, y as f32), depth, normal, vertex_position, world_position, tex_coords)
+            });
         }
     }
 
@@ -1,12 +1,14 @@
-use nalgebra_glm::Vec3;
+use nalgebra_glm::{Vec2, Vec3};
 use crate::color::Color;
 
 #[derive(Clone, Debug)]
 pub struct Vertex {
     pub position: Vec3,     // object space
     pub normal: Vec3,
+    pub tex_coords: Vec2,
 
     pub transformed_position: Vec3, // screen space
+    pub world_position: Vec3,
     pub transformed_normal: Vec3,   // world space normal transformed
     pub color: Color,
 }
@@ -16,9 +18,15 @@ impl Vertex {
         Self {
             position,
             normal,
+            tex_coords: Vec2::zeros(),
             transformed_position: position,
+            world_position: position,
             transformed_normal: normal,
             color: Color::new(0,0,0),
         }
     }
+
+    pub fn with_tex_coords(self, tex_coords: Vec2) -> Self {
+        Self { tex_coords, ..self }
+    }
 }